@@ -1,3 +1,4 @@
+use util::Float;
 use vec3::Vec3;
 use ray::Ray;
 
@@ -41,8 +42,38 @@ impl AABB {
         }
     }
 
+    /// Returns the center of the bounding box
+    pub fn center(&self) -> Vec3 {
+        match self {
+            &AABB::BBox { min, max } => (min + max) / 2.0,
+            &AABB::None => panic!("An empty bounding box has no center!"),
+        }
+    }
+
+    /// Returns the vector from the minimum to the maximum corner
+    pub fn diagonal(&self) -> Vec3 {
+        match self {
+            &AABB::BBox { min, max } => max - min,
+            &AABB::None => Vec3::zeros(),
+        }
+    }
+
+    /// Returns the squared distance from a point to the nearest point in the
+    /// bounding box, zero if the point is inside
+    pub fn distance2(&self, p: Vec3) -> Float {
+        match self {
+            &AABB::BBox { min, max } => {
+                let dx = fmax(fmax(min.x - p.x, p.x - max.x), 0.0);
+                let dy = fmax(fmax(min.y - p.y, p.y - max.y), 0.0);
+                let dz = fmax(fmax(min.z - p.z, p.z - max.z), 0.0);
+                dx * dx + dy * dy + dz * dz
+            },
+            &AABB::None => Float::MAX,
+        }
+    }
+
     /// Returns true if the ray hits the bounding box
-    pub fn hit(&self, ray: Ray, tmin: f64, tmax: f64) -> bool {
+    pub fn hit(&self, ray: Ray, tmin: Float, tmax: Float) -> bool {
         match self {
             &AABB::BBox { min, max } => {
                 let mut tmin = tmin;
@@ -64,10 +95,10 @@ impl AABB {
     }
 }
 
-fn fmin(a: f64, b: f64) -> f64 {
+fn fmin(a: Float, b: Float) -> Float {
     if a < b { a } else { b }
 }
 
-fn fmax(a: f64, b: f64) -> f64 {
+fn fmax(a: Float, b: Float) -> Float {
     if a > b { a } else { b }
 }
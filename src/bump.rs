@@ -0,0 +1,84 @@
+use util::Float;
+use vec3::Vec3;
+use geo::Intersection;
+use texture::Texture;
+use image_map::{Filter, ImageMap, Wrap};
+use std::path::Path;
+use std::sync::Arc;
+
+/// The step in surface coordinates over which height derivatives are taken
+const BUMP_DELTA: Float = 0.0005;
+
+#[derive(Debug, Clone)]
+pub enum Bump {
+    /// A tangent space normal map, with red along u, green along v and blue
+    /// along the surface normal
+    Normal {
+        map: Arc<ImageMap>,
+    },
+    /// A height field whose luminance, times a scale, displaces the surface
+    /// along its normal
+    Height {
+        texture: Texture,
+        scale: Float,
+    },
+}
+
+impl Bump {
+    /// Creates a new normal map from an image
+    pub fn normal_map<P: AsRef<Path>>(path: P) -> Self {
+        Bump::Normal { map: Arc::new(ImageMap::load(path)) }
+    }
+
+    /// Creates a new bump map from a height texture
+    pub fn height(texture: Texture, scale: Float) -> Self {
+        Bump::Height { texture, scale }
+    }
+
+    /// Returns the intersection with its normal perturbed by the map, facing
+    /// the same side of the surface as the original normal
+    pub fn perturb(&self, i: &Intersection) -> Intersection {
+        let normal = match self {
+            &Bump::Normal { ref map } => {
+                let (tangent, bitangent) = tangents(i);
+                let n = map.lookup(i.u, i.v, i.uv_width(), Filter::Trilinear, Wrap::Repeat);
+                let n = 2.0 * n - Vec3::ones();
+                n.x * tangent + n.y * bitangent + n.z * i.normal
+            },
+            &Bump::Height { ref texture, scale } => {
+                let height = |i: &Intersection| texture.value(i).luminance() * scale;
+                let shifted = |du: Float, dv: Float| Intersection {
+                    point: i.point + du * i.dpdu + dv * i.dpdv,
+                    u:     i.u + du,
+                    v:     i.v + dv,
+                    ..i.clone()
+                };
+                let h = height(i);
+                let dhdu = (height(&shifted(BUMP_DELTA, 0.0)) - h) / BUMP_DELTA;
+                let dhdv = (height(&shifted(0.0, BUMP_DELTA)) - h) / BUMP_DELTA;
+                Vec3::cross(i.dpdu + dhdu * i.normal, i.dpdv + dhdv * i.normal)
+            },
+        };
+        let normal = if Vec3::dot(normal, i.normal) < 0.0 { -normal } else { normal };
+        if normal.mag() <= 0.0 {
+            return i.clone();
+        }
+        Intersection { normal: normal.unit(), ..i.clone() }
+    }
+}
+
+/// Returns the unit tangent along u and bitangent along v of an intersection,
+/// made orthogonal to its normal
+fn tangents(i: &Intersection) -> (Vec3, Vec3) {
+    let tangent = i.dpdu - Vec3::dot(i.dpdu, i.normal) * i.normal;
+    if tangent.mag() <= 0.0 {
+        return Vec3::basis(i.normal);
+    }
+    let tangent = tangent.unit();
+    let bitangent = Vec3::cross(i.normal, tangent);
+    if Vec3::dot(bitangent, i.dpdv) < 0.0 {
+        (tangent, -bitangent)
+    } else {
+        (tangent, bitangent)
+    }
+}
@@ -1,6 +1,6 @@
-use util::rand;
+use util::{rand, Float};
 use vec3::Vec3;
-use ray::Ray;
+use ray::{Ray, RayDifferential};
 
 #[derive(Debug, Clone, Copy)]
 pub struct Camera {
@@ -13,13 +13,13 @@ pub struct Camera {
     /// The vertical distance across screenspace
     pub vrt: Vec3,
     /// The lens length
-    pub lens: f64,
+    pub lens: Float,
     /// Shutter opening time
-    pub time_open: f64,
+    pub time_open: Float,
     /// Shutter closing time
-    pub time_close: f64,
+    pub time_close: Float,
     /// The time difference
-    pub time_difference: f64,
+    pub time_difference: Float,
 }
 
 impl Camera {
@@ -39,10 +39,10 @@ impl Camera {
         from: Vec3,
         to: Vec3,
         vup: Vec3,
-        vfov: f64,
-        aspect: f64,
-        defocus: Option<(f64, f64)>,
-        time: Option<(f64, f64)>
+        vfov: Float,
+        aspect: Float,
+        defocus: Option<(Float, Float)>,
+        time: Option<(Float, Float)>
     ) -> Self {
         let height = (vfov.to_radians() / 2.0).tan() * 2.0;
         let width  = aspect * height;
@@ -86,18 +86,35 @@ impl Camera {
         }
     }
 
-    /// Returns an array given a screenspace coordinate (u, v)
+    /// Returns a ray given a screenspace coordinate (u, v), along with the
+    /// rays through the neighbouring pixels sharing its point on the lens
     ///
     /// # Arguments
-    /// * `u` - x screenspace coordinate
-    /// * `v` - y screenspace coordinate
+    /// * `u`  - x screenspace coordinate
+    /// * `v`  - y screenspace coordinate
+    /// * `du` - the width of a pixel in screenspace
+    /// * `dv` - the height of a pixel in screenspace
     ///
-    pub fn ray(&self, u: f64, v: f64) -> Ray {
-        let Vec3 { x, y, .. } = self.lens * Vec3::rand_disc();
-        let off    = u * x + v * y;
+    pub fn ray_differential(&self, u: Float, v: Float, du: Float, dv: Float) -> Ray {
+        let lens = self.lens * Vec3::rand_disc();
+        let time = self.time_open + rand() * self.time_difference;
+        let (origin, dir) = self.through(lens, u, v);
+        let (rx_origin, rx_dir) = self.through(lens, u + du, v);
+        let (ry_origin, ry_dir) = self.through(lens, u, v + dv);
+        Ray::new(origin, dir, time).with_differential(RayDifferential {
+            rx_origin,
+            rx_dir: rx_dir.unit(),
+            ry_origin,
+            ry_dir: ry_dir.unit(),
+        })
+    }
+
+    /// Returns the origin and direction of a ray through a point on the lens
+    /// and a screenspace coordinate (u, v)
+    fn through(&self, lens: Vec3, u: Float, v: Float) -> (Vec3, Vec3) {
+        let off    = u * lens.x + v * lens.y;
         let origin = self.origin - off;
         let dir    = self.llc + u * self.hrz + v * self.vrt - self.origin + off;
-        let time   = self.time_open + rand() * self.time_difference;
-        Ray::new(origin, dir, time)
+        (origin, dir)
     }
 }
@@ -1,5 +1,8 @@
 extern crate image;
 
+use util::Float;
+use vec3::Vec3;
+
 #[derive(Debug, Clone, Copy)]
 pub struct Color {
     pub r: u8,
@@ -28,7 +31,7 @@ impl Color {
     /// * `b`     - the blue channel
     /// * `gamma` - the gamma value
     ///
-    pub fn fnew(r: f64, g: f64, b: f64, gamma: f64) -> Self {
+    pub fn fnew(r: Float, g: Float, b: Float, gamma: Float) -> Self {
         Color {
             r: (r.powf(gamma) * 255.99) as u8,
             g: (g.powf(gamma) * 255.99) as u8,
@@ -63,17 +66,24 @@ impl Color {
 
     /// Get a Pixel value in black and white
     pub fn bw(&self) -> image::Rgb<u8> {
-        let c = (((self.r as f64)
-                + (self.g as f64)
-                + (self.b as f64)
+        let c = (((self.r as Float)
+                + (self.g as Float)
+                + (self.b as Float)
         ) / 3.0) as u8;
-        image::Rgb { data: [c, c, c] }
+        image::Rgb([c, c, c])
     }
 
     /// Get a Pixel value in red green and blue
     pub fn rgb(&self) -> image::Rgb<u8> {
-        image::Rgb {
-            data: [self.r, self.g, self.b],
-        }
+        image::Rgb([self.r, self.g, self.b])
     }
 }
+
+/// Converts a CIE XYZ color to linear sRGB
+pub fn xyz_to_rgb(xyz: Vec3) -> Vec3 {
+    Vec3::new(
+        3.2406 * xyz.x - 1.5372 * xyz.y - 0.4986 * xyz.z,
+        -0.9689 * xyz.x + 1.8758 * xyz.y + 0.0415 * xyz.z,
+        0.0557 * xyz.x - 0.2040 * xyz.y + 1.0570 * xyz.z,
+    )
+}
@@ -1,9 +1,12 @@
 extern crate toml;
 extern crate image;
 
+use util::Float;
 use scene::Scene;
 use frame::Frame;
-use image::{ PNG, JPEG, GIF, WEBP, BMP, ICO, };
+use environment::Environment;
+use ies::IesProfile;
+use image::ImageFormat;
 use std::fmt::{ Display, Formatter };
 use scenes::{
     empty,
@@ -12,6 +15,24 @@ use scenes::{
     random_scene,
     two_spheres,
     two_perlin_spheres,
+    simple_light,
+    cornell_box,
+    analytic_lights,
+    light_panels,
+    ies_light,
+    microfacet,
+    principled,
+    coated,
+    mix,
+    tinted_glass,
+    dispersion,
+    thin_film,
+    subsurface,
+    rough_diffuse,
+    bump_map,
+    uv_checker,
+    image_texture,
+    marble,
 };
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -28,6 +49,28 @@ pub struct Config {
     width: u32,
     /// The render height
     height: u32,
+    /// The background, either sky, black, daylight, or the path to an
+    /// equirectangular .hdr or .exr environment map, defaults to the scene's
+    /// own
+    background: Option<String>,
+    /// The rotation of the environment map about the y axis in degrees
+    background_rotation: Option<Float>,
+    /// The factor the environment map or daylight radiance is scaled by
+    background_intensity: Option<Float>,
+    /// The daylight sun's angle above the horizon in degrees
+    sun_elevation: Option<Float>,
+    /// The daylight sun's angle from the x axis towards the z axis in degrees
+    sun_azimuth: Option<Float>,
+    /// The daylight atmosphere's haziness, from 2 for clear to 10 for hazy
+    turbidity: Option<Float>,
+    /// The path to an IES LM-63 photometric profile for the ies_light scene
+    ies: Option<String>,
+    /// Whether to trace light spectrally rather than in RGB, defaults to RGB
+    spectral: Option<bool>,
+    /// The path to a tangent space normal map for the bump_map scene
+    normal_map: Option<String>,
+    /// The path to a .png or .jpg image for the image_texture scene
+    texture: Option<String>,
 }
 
 impl Config {
@@ -40,44 +83,106 @@ impl Config {
             samples: 1,
             width:   400,
             height:  200,
+            background: None,
+            background_rotation: None,
+            background_intensity: None,
+            sun_elevation: None,
+            sun_azimuth: None,
+            turbidity: None,
+            ies: None,
+            spectral: None,
+            normal_map: None,
+            texture: None,
         }
     }
 
     /// The output format, either PNG, JPEG, GIF, WEBP, BMP, PPM, or ICO
     pub fn format(&self) -> image::ImageFormat {
         match self.format.to_uppercase().as_ref() {
-            "PNG"  => PNG,
-            "JPG"  => JPEG,
-            "JPEG" => JPEG,
-            "GIF"  => GIF,
-            "WEBP" => WEBP,
-            "BMP"  => BMP,
-            "ICO"  => ICO,
+            "PNG"  => ImageFormat::Png,
+            "JPG"  => ImageFormat::Jpeg,
+            "JPEG" => ImageFormat::Jpeg,
+            "GIF"  => ImageFormat::Gif,
+            "WEBP" => ImageFormat::WebP,
+            "BMP"  => ImageFormat::Bmp,
+            "ICO"  => ImageFormat::Ico,
             other  => panic!("Unrecognized format {}!", other),
         }
     }
 
     /// Get the scene to render
     pub fn scene(&self) -> Scene {
-        match self.scene.to_lowercase().as_ref() {
+        let scene = match self.scene.to_lowercase().as_ref() {
             "empty"              => empty(),
             "sphere"             => sphere(),
             "spheres"            => spheres(),
             "random_scene"       => random_scene(self.aspect()),
             "two_spheres"        => two_spheres(self.aspect()),
             "two_perlin_spheres" => two_perlin_spheres(self.aspect()),
+            "simple_light"       => simple_light(self.aspect()),
+            "cornell_box"        => cornell_box(self.aspect()),
+            "analytic_lights"    => analytic_lights(self.aspect()),
+            "light_panels"       => light_panels(self.aspect()),
+            "ies_light"          => ies_light(self.aspect(), IesProfile::load(
+                self.ies.as_ref().expect("The ies_light scene requires an ies profile!")
+            )),
+            "microfacet"         => microfacet(self.aspect()),
+            "principled"         => principled(self.aspect()),
+            "coated"             => coated(self.aspect()),
+            "mix"                => mix(self.aspect()),
+            "tinted_glass"       => tinted_glass(self.aspect()),
+            "dispersion"         => dispersion(self.aspect()),
+            "thin_film"          => thin_film(self.aspect()),
+            "subsurface"         => subsurface(self.aspect()),
+            "rough_diffuse"      => rough_diffuse(self.aspect()),
+            "bump_map"           => bump_map(
+                self.aspect(),
+                self.normal_map.as_ref().map(|path| path.as_ref()),
+            ),
+            "uv_checker"         => uv_checker(self.aspect()),
+            "image_texture"      => image_texture(self.aspect(), self.texture
+                .as_ref()
+                .expect("The image_texture scene requires a texture!")
+            ),
+            "marble"             => marble(self.aspect()),
             other                => panic!("Unrecognized scene {}!", other),
+        };
+        match self.environment() {
+            Some(environment) => scene.with_environment(environment),
+            None              => scene,
         }
     }
 
+    /// Get the environment overriding the scene's own, if any
+    pub fn environment(&self) -> Option<Environment> {
+        self.background.as_ref().map(|background| {
+            match background.to_lowercase().as_ref() {
+                "sky"      => Environment::gradient(),
+                "black"    => Environment::black(),
+                "daylight" => Environment::sky(
+                    self.sun_elevation.unwrap_or(45.0),
+                    self.sun_azimuth.unwrap_or(0.0),
+                    self.turbidity.unwrap_or(3.0),
+                    self.background_intensity.unwrap_or(1.0),
+                ),
+                _          => Environment::map(
+                    background,
+                    self.background_rotation.unwrap_or(0.0),
+                    self.background_intensity.unwrap_or(1.0),
+                ),
+            }
+        })
+    }
+
     /// Get the aspect ratio
-    pub fn aspect(&self) -> f64 {
-        (self.width as f64) / (self.height as f64)
+    pub fn aspect(&self) -> Float {
+        (self.width as Float) / (self.height as Float)
     }
 
     /// Get the frame
     pub fn frame(&self) -> Frame {
         Frame::new(self.width, self.height, self.samples)
+            .with_spectral(self.spectral.unwrap_or(false))
     }
 
     /// Runs the given config
@@ -97,6 +202,8 @@ impl Display for Config {
 \n    samples: {}\
 \n    width:   {}\
 \n    height:  {}\
+\n    background: {}\
+\n    spectral: {}\
 \n]",
             self.format,
             self.out,
@@ -104,6 +211,8 @@ impl Display for Config {
             self.samples,
             self.width,
             self.height,
+            self.background.as_ref().map_or("scene", |b| b.as_ref()),
+            self.spectral.unwrap_or(false),
         )
     }
 }
\ No newline at end of file
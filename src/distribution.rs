@@ -0,0 +1,115 @@
+use util::Float;
+
+#[derive(Debug, Clone)]
+pub struct Distribution1D {
+    /// The piecewise-constant function being sampled
+    func: Vec<Float>,
+    /// The cumulative distribution of the function, one longer than `func`
+    cdf: Vec<Float>,
+    /// The integral of the function over [0, 1]
+    integral: Float,
+}
+
+impl Distribution1D {
+    /// Creates a new distribution proportional to a piecewise-constant
+    /// function over [0, 1]
+    pub fn new(func: Vec<Float>) -> Self {
+        let n = func.len();
+        let mut cdf = vec![0.0; n + 1];
+        for i in 0..n {
+            cdf[i + 1] = cdf[i] + func[i].abs() / (n as Float);
+        }
+        let integral = cdf[n];
+        // A function of all zeros is sampled uniformly
+        for i in 1..(n + 1) {
+            cdf[i] = if integral == 0.0 {
+                (i as Float) / (n as Float)
+            } else {
+                cdf[i] / integral
+            };
+        }
+        Distribution1D { func, cdf, integral }
+    }
+
+    /// The number of pieces in the function
+    pub fn count(&self) -> usize {
+        self.func.len()
+    }
+
+    /// The integral of the function over [0, 1]
+    pub fn integral(&self) -> Float {
+        self.integral
+    }
+
+    /// Samples a point in [0, 1] given a uniform random number. Returns the
+    /// point, its density, and the index of the piece it lies in.
+    pub fn sample(&self, u: Float) -> (Float, Float, usize) {
+        // Find the last cdf entry less than or equal to u
+        let mut lo = 0;
+        let mut hi = self.cdf.len() - 1;
+        while hi - lo > 1 {
+            let mid = (lo + hi) / 2;
+            if self.cdf[mid] <= u { lo = mid } else { hi = mid }
+        }
+        let offset = lo.min(self.count() - 1);
+        let width  = self.cdf[offset + 1] - self.cdf[offset];
+        let du = if width > 0.0 { (u - self.cdf[offset]) / width } else { 0.0 };
+        let x  = ((offset as Float) + du) / (self.count() as Float);
+        (x, self.pdf(offset), offset)
+    }
+
+    /// Returns the density of sampling a point in the given piece
+    pub fn pdf(&self, offset: usize) -> Float {
+        if self.integral == 0.0 {
+            1.0
+        } else {
+            self.func[offset].abs() / self.integral
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Distribution2D {
+    /// The distributions along u for each row in v
+    conditional: Vec<Distribution1D>,
+    /// The distribution of rows along v
+    marginal: Distribution1D,
+}
+
+impl Distribution2D {
+    /// Creates a new distribution proportional to a piecewise-constant
+    /// function over [0, 1]^2, given in rows of `nu` values
+    pub fn new(func: &[Float], nu: usize, nv: usize) -> Self {
+        let conditional: Vec<Distribution1D> = (0..nv)
+            .map(|v| Distribution1D::new(func[v * nu..(v + 1) * nu].to_vec()))
+            .collect();
+        let marginal = Distribution1D::new(conditional
+            .iter()
+            .map(|row| row.integral())
+            .collect()
+        );
+        Distribution2D { conditional, marginal }
+    }
+
+    /// Samples a point in [0, 1]^2 given two uniform random numbers. Returns
+    /// the point and its density.
+    pub fn sample(&self, u0: Float, u1: Float) -> ((Float, Float), Float) {
+        let (v, pdf_v, row) = self.marginal.sample(u1);
+        let (u, pdf_u, _)   = self.conditional[row].sample(u0);
+        ((u, v), pdf_u * pdf_v)
+    }
+
+    /// Returns the density of sampling the given point
+    pub fn pdf(&self, u: Float, v: Float) -> Float {
+        let nv = self.marginal.count();
+        let row = ((v * nv as Float) as usize).min(nv - 1);
+        let nu = self.conditional[row].count();
+        let col = ((u * nu as Float) as usize).min(nu - 1);
+        if self.marginal.integral() == 0.0 {
+            1.0
+        } else {
+            self.conditional[row].pdf(col) * self.conditional[row].integral()
+                / self.marginal.integral()
+        }
+    }
+}
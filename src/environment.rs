@@ -0,0 +1,193 @@
+use util::{rand, Float, PI};
+use vec3::Vec3;
+use ray::Ray;
+use distribution::Distribution2D;
+use sky::Sky;
+use spectrum::illuminant;
+use std::path::Path;
+use std::sync::Arc;
+use hdr;
+use exr;
+
+#[derive(Debug, Clone)]
+pub enum Environment {
+    /// A sky gradient from white at the horizon to blue overhead
+    Gradient,
+    /// A single color in every direction, black for scenes lit only by lights
+    Solid {
+        color: Vec3,
+    },
+    /// An equirectangular image surrounding the scene
+    Map {
+        map: Arc<EnvironmentMap>,
+    },
+    /// An analytic daylight sky and sun disk
+    Sky {
+        sky: Sky,
+    },
+}
+
+#[derive(Debug)]
+pub struct EnvironmentMap {
+    /// The image width in pixels
+    width: usize,
+    /// The image height in pixels
+    height: usize,
+    /// The image pixels in rows from the top left
+    pixels: Vec<Vec3>,
+    /// The rotation of the image about the y axis in radians
+    rotation: Float,
+    /// The factor the image radiance is scaled by
+    scale: Float,
+    /// The distribution of directions proportional to the image radiance
+    distribution: Distribution2D,
+}
+
+impl Environment {
+    /// Creates the default sky gradient environment
+    pub fn gradient() -> Self {
+        Environment::Gradient
+    }
+
+    /// Creates a solid color environment
+    pub fn solid(color: Vec3) -> Self {
+        Environment::Solid { color }
+    }
+
+    /// Creates a black environment
+    pub fn black() -> Self {
+        Environment::solid(Vec3::zeros())
+    }
+
+    /// Creates a Preetham daylight sky environment, see `Sky::new`
+    pub fn sky(elevation: Float, azimuth: Float, turbidity: Float, scale: Float) -> Self {
+        Environment::Sky { sky: Sky::new(elevation, azimuth, turbidity, scale) }
+    }
+
+    /// Creates an environment from an equirectangular .hdr or .exr image
+    ///
+    /// # Arguments
+    /// * `path`     - the path to the image
+    /// * `rotation` - the rotation of the image about the y axis in degrees
+    /// * `scale`    - the factor the image radiance is scaled by
+    ///
+    pub fn map<P: AsRef<Path>>(path: P, rotation: Float, scale: Float) -> Self {
+        let extension = path.as_ref()
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| extension.to_lowercase());
+        let (width, height, pixels) = match extension.as_ref().map(|e| e.as_ref()) {
+            Some("hdr") => hdr::load(path),
+            Some("exr") => exr::load(path),
+            _ => panic!("Unrecognized environment map {}!", path.as_ref().display()),
+        };
+        // Weight each row by its solid angle so the poles, which are
+        // stretched across the image, are not oversampled
+        let mut func = Vec::with_capacity(width * height);
+        for y in 0..height {
+            let sin = (PI * ((y as Float) + 0.5) / (height as Float)).sin();
+            for x in 0..width {
+                func.push(pixels[y * width + x].luminance().max(0.0) * sin);
+            }
+        }
+        Environment::Map {
+            map: Arc::new(EnvironmentMap {
+                width:        width,
+                height:       height,
+                pixels:       pixels,
+                rotation:     rotation.to_radians(),
+                scale:        scale,
+                distribution: Distribution2D::new(&func, width, height),
+            }),
+        }
+    }
+
+    /// Returns the radiance arriving along a ray that escapes the scene
+    ///
+    /// # Arguments
+    /// * `ray`         - the escaping ray
+    /// * `wavelengths` - the wavelengths traced by a spectral path, None when
+    ///                   tracing in RGB
+    ///
+    pub fn value(&self, ray: Ray, wavelengths: Option<Vec3>) -> Vec3 {
+        let radiance = match self {
+            // Handle the sky gradient
+            &Environment::Gradient => {
+                let t = (ray.dir.y + 1.0) * 0.5;
+                (1.0 - t) * Vec3::ones() + t * Vec3::new(0.5, 0.7, 1.0)
+            },
+            // Handle solid colors (trivial)
+            &Environment::Solid { color } => color,
+            // Handle environment maps
+            &Environment::Map { ref map } => {
+                let (u, v) = map.uv(ray.dir);
+                let x = ((u * map.width as Float) as usize).min(map.width - 1);
+                let y = ((v * map.height as Float) as usize).min(map.height - 1);
+                map.pixels[y * map.width + x] * map.scale
+            },
+            // Handle daylight skies
+            &Environment::Sky { ref sky } => sky.radiance(ray.dir),
+        };
+        illuminant(radiance, wavelengths)
+    }
+
+    /// Returns true if the environment should be sampled as a light
+    pub fn is_sampled(&self) -> bool {
+        match self {
+            &Environment::Map { .. } => true,
+            &Environment::Sky { .. } => true,
+            _ => false,
+        }
+    }
+
+    /// Samples a direction proportional to the radiance of the environment,
+    /// skies only sample their sun disk
+    pub fn sample(&self) -> Vec3 {
+        match self {
+            &Environment::Map { ref map } => {
+                let ((u, v), _) = map.distribution.sample(rand(), rand());
+                map.dir(u, v)
+            },
+            &Environment::Sky { ref sky } => sky.sample(),
+            other => panic!("{:?} can not be sampled!", other),
+        }
+    }
+
+    /// Returns the solid angle density with which `sample` produces a
+    /// direction
+    pub fn pdf(&self, dir: Vec3) -> Float {
+        match self {
+            &Environment::Map { ref map } => {
+                let (u, v) = map.uv(dir);
+                let sin = (PI * v).sin();
+                if sin <= 0.0 {
+                    return 0.0;
+                }
+                map.distribution.pdf(u, v) / (2.0 * PI * PI * sin)
+            },
+            &Environment::Sky { ref sky } => sky.pdf(dir),
+            _ => 0.0,
+        }
+    }
+}
+
+impl EnvironmentMap {
+    /// Returns the image coordinates in [0, 1]^2 a direction maps to
+    fn uv(&self, dir: Vec3) -> (Float, Float) {
+        let theta = dir.y.max(-1.0).min(1.0).acos();
+        let phi   = dir.z.atan2(dir.x) - self.rotation;
+        let phi   = phi - 2.0 * PI * (phi / (2.0 * PI)).floor();
+        (phi / (2.0 * PI), theta / PI)
+    }
+
+    /// Returns the direction image coordinates in [0, 1]^2 map to
+    fn dir(&self, u: Float, v: Float) -> Vec3 {
+        let theta = v * PI;
+        let phi   = u * 2.0 * PI + self.rotation;
+        Vec3::new(
+            theta.sin() * phi.cos(),
+            theta.cos(),
+            theta.sin() * phi.sin(),
+        )
+    }
+}
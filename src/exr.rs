@@ -0,0 +1,226 @@
+use util::Float;
+use vec3::Vec3;
+use std::path::Path;
+use std::fs::File;
+use std::io::Read;
+
+/// Loads an OpenEXR (.exr) image, returning its width, height and pixels in
+/// rows from the top left. Only scanline images stored without compression
+/// (compression type NONE) with half or float R, G and B channels are
+/// supported, others and truncated files panic.
+pub fn load<P: AsRef<Path>>(path: P) -> (usize, usize, Vec<Vec3>) {
+    let mut bytes = Vec::new();
+    File::open(path)
+        .expect("Failed to open EXR file!")
+        .read_to_end(&mut bytes)
+        .expect("Failed to read EXR file!");
+    decode(&bytes)
+}
+
+/// Decodes the bytes of an uncompressed OpenEXR image, see `load`
+fn decode(bytes: &[u8]) -> (usize, usize, Vec<Vec3>) {
+    let mut pos = 0;
+    if read_i32(bytes, &mut pos) != 20000630 {
+        panic!("Not an OpenEXR file!");
+    }
+    if read_i32(bytes, &mut pos) & 0x200 != 0 {
+        panic!("Tiled EXR files are unsupported!");
+    }
+    // Read the header attributes
+    let mut channels = Vec::new();
+    let mut window = None;
+    loop {
+        let name = read_string(bytes, &mut pos);
+        if name.is_empty() {
+            break;
+        }
+        let _kind = read_string(bytes, &mut pos);
+        let size  = read_i32(bytes, &mut pos);
+        if size < 0 || pos + size as usize > bytes.len() {
+            panic!("EXR attribute '{}' of {} bytes overruns the file!", name, size);
+        }
+        let end   = pos + size as usize;
+        match name.as_ref() {
+            "channels" => {
+                loop {
+                    let channel = read_string(bytes, &mut pos);
+                    if channel.is_empty() {
+                        break;
+                    }
+                    let pixel_type = read_i32(bytes, &mut pos);
+                    // Skip linearity, reserved bytes, and sampling rates
+                    take(bytes, &mut pos, 4 + 8);
+                    channels.push((channel, pixel_type));
+                }
+            },
+            "compression" => if take(bytes, &mut pos, 1)[0] != 0 {
+                panic!("Compressed EXR files are unsupported!");
+            },
+            "dataWindow" => {
+                let x_min = read_i32(bytes, &mut pos);
+                let y_min = read_i32(bytes, &mut pos);
+                let x_max = read_i32(bytes, &mut pos);
+                let y_max = read_i32(bytes, &mut pos);
+                window = Some((x_min, y_min, x_max, y_max));
+            },
+            _ => (),
+        }
+        pos = end;
+    }
+    let (x_min, y_min, x_max, y_max) = window.expect("EXR file has no data window!");
+    if x_max < x_min || y_max < y_min {
+        panic!("EXR data window ({}, {}) to ({}, {}) is empty!", x_min, y_min, x_max, y_max);
+    }
+    let width  = (x_max as i64 - x_min as i64 + 1) as usize;
+    let height = (y_max as i64 - y_min as i64 + 1) as usize;
+    // Skip the offset table, uncompressed files store one scanline per block
+    take(bytes, &mut pos, 8 * height);
+    let mut pixels = vec![Vec3::zeros(); width * height];
+    for _ in 0..height {
+        let y = read_i32(bytes, &mut pos) as i64 - y_min as i64;
+        if y < 0 || y >= height as i64 {
+            panic!("EXR scanline {} is outside the data window!", y + y_min as i64);
+        }
+        let y = y as usize;
+        let _size = read_i32(bytes, &mut pos);
+        // Channels are stored one after another in alphabetical order
+        for &(ref channel, pixel_type) in channels.iter() {
+            for x in 0..width {
+                let value = match pixel_type {
+                    1 => half_to_float(read_u16(bytes, &mut pos)),
+                    2 => f32::from_bits(read_i32(bytes, &mut pos) as u32) as Float,
+                    _ => panic!("Unsupported EXR pixel type {}!", pixel_type),
+                };
+                let p = pixels[y * width + x];
+                pixels[y * width + x] = match channel.as_ref() {
+                    "R" => Vec3::new(value, p.y, p.z),
+                    "G" => Vec3::new(p.x, value, p.z),
+                    "B" => Vec3::new(p.x, p.y, value),
+                    _   => p,
+                };
+            }
+        }
+    }
+    (width, height, pixels)
+}
+
+/// Takes the next `n` bytes, panicking if the file ends before them
+fn take<'a>(bytes: &'a [u8], pos: &mut usize, n: usize) -> &'a [u8] {
+    if bytes.len() - *pos < n {
+        panic!("EXR file is truncated, expected {} more bytes at offset {}!", n, *pos);
+    }
+    let b = &bytes[*pos..*pos + n];
+    *pos += n;
+    b
+}
+
+/// Reads a null terminated string
+fn read_string(bytes: &[u8], pos: &mut usize) -> String {
+    let len = bytes[*pos..].iter().position(|&b| b == 0)
+        .expect("EXR file is truncated, string is not null terminated!");
+    let string = String::from_utf8_lossy(take(bytes, pos, len)).to_string();
+    *pos += 1;
+    string
+}
+
+/// Reads a little endian 32 bit integer
+fn read_i32(bytes: &[u8], pos: &mut usize) -> i32 {
+    let b = take(bytes, pos, 4);
+    (b[0] as i32) | (b[1] as i32) << 8 | (b[2] as i32) << 16 | (b[3] as i32) << 24
+}
+
+/// Reads a little endian 16 bit integer
+fn read_u16(bytes: &[u8], pos: &mut usize) -> u16 {
+    let b = take(bytes, pos, 2);
+    (b[0] as u16) | (b[1] as u16) << 8
+}
+
+/// Converts a half precision float to a `Float`
+fn half_to_float(h: u16) -> Float {
+    let sign     = if h & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = ((h >> 10) & 0x1f) as i32;
+    let mantissa = (h & 0x3ff) as Float;
+    sign * match exponent {
+        0  => mantissa * (2.0 as Float).powi(-24),
+        31 => if mantissa == 0.0 { Float::INFINITY } else { Float::NAN },
+        _  => (1.0 + mantissa / 1024.0) * (2.0 as Float).powi(exponent - 15),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attribute(bytes: &mut Vec<u8>, name: &str, kind: &str, value: &[u8]) {
+        bytes.extend_from_slice(name.as_bytes());
+        bytes.push(0);
+        bytes.extend_from_slice(kind.as_bytes());
+        bytes.push(0);
+        bytes.extend_from_slice(&(value.len() as i32).to_le_bytes());
+        bytes.extend_from_slice(value);
+    }
+
+    /// Builds a 2 by 1 uncompressed image with float R, G and B channels
+    fn image() -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&20000630i32.to_le_bytes());
+        bytes.extend_from_slice(&2i32.to_le_bytes());
+        let mut channels = Vec::new();
+        for name in ["B", "G", "R"].iter() {
+            channels.extend_from_slice(name.as_bytes());
+            channels.push(0);
+            channels.extend_from_slice(&2i32.to_le_bytes());
+            channels.extend_from_slice(&[0; 4 + 8]);
+        }
+        channels.push(0);
+        attribute(&mut bytes, "channels", "chlist", &channels);
+        attribute(&mut bytes, "compression", "compression", &[0]);
+        let mut window = Vec::new();
+        for v in [0i32, 0, 1, 0].iter() {
+            window.extend_from_slice(&v.to_le_bytes());
+        }
+        attribute(&mut bytes, "dataWindow", "box2i", &window);
+        bytes.push(0);
+        bytes.extend_from_slice(&[0; 8]);
+        bytes.extend_from_slice(&0i32.to_le_bytes());
+        bytes.extend_from_slice(&24i32.to_le_bytes());
+        for v in [0.5f32, 0.25, 1.0, 2.0, 3.0, 4.0].iter() {
+            bytes.extend_from_slice(&v.to_bits().to_le_bytes());
+        }
+        bytes
+    }
+
+    #[test]
+    fn decodes_uncompressed_float_channels() {
+        let (width, height, pixels) = decode(&image());
+        assert_eq!((width, height), (2, 1));
+        assert_eq!((pixels[0].x, pixels[0].y, pixels[0].z), (3.0, 1.0, 0.5));
+        assert_eq!((pixels[1].x, pixels[1].y, pixels[1].z), (4.0, 2.0, 0.25));
+    }
+
+    #[test]
+    #[should_panic(expected = "EXR file is truncated")]
+    fn rejects_truncated_pixels() {
+        let mut bytes = image();
+        bytes.pop();
+        decode(&bytes);
+    }
+
+    #[test]
+    #[should_panic(expected = "overruns the file")]
+    fn rejects_truncated_header() {
+        let bytes = image();
+        decode(&bytes[..40]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Compressed EXR files are unsupported")]
+    fn rejects_compressed_files() {
+        let mut bytes = image();
+        let at = bytes.windows(12).position(|w| w == b"compression\0").unwrap();
+        // Skip the name, type, and size to the compression byte
+        let value = at + 12 + 12 + 4;
+        bytes[value] = 3;
+        decode(&bytes);
+    }
+}
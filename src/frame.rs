@@ -3,12 +3,12 @@ use std::path::Path;
 use std::fs::File;
 use std::io::{ Write, stdout };
 use vec3::Vec3;
-use util::rand;
+use util::{rand, Float};
 use shader::shader;
 use scene::Scene;
-use image::{ImageBuffer, ImageRgb8, Rgb, ImageFormat, };
+use image::{ImageBuffer, Rgb, ImageFormat, };
 
-const GAMMA: f64 = 0.5;
+const GAMMA: Float = 0.5;
 
 #[derive(Debug)]
 pub struct Frame {
@@ -18,6 +18,8 @@ pub struct Frame {
     pub height: u32,
     /// The number of samples to take per pixel
     pub samples: u32,
+    /// Whether to trace light spectrally rather than in RGB
+    pub spectral: bool,
     /// A vector of all coordinates in the frame
     coords: Vec<(u32, u32)>,
     /// The frame buffer
@@ -46,11 +48,17 @@ impl Frame {
             width: width,
             height: height,
             samples: samples,
+            spectral: false,
             coords: coords,
             buffer: ImageBuffer::new(width, height),
         }
     }
 
+    /// Sets whether the frame is rendered spectrally rather than in RGB
+    pub fn with_spectral(self, spectral: bool) -> Self {
+        Frame { spectral, ..self }
+    }
+
     /// Get the next frame given a scene.
     ///
     /// # Arguments
@@ -67,13 +75,14 @@ impl Frame {
                 for _ in 0..self.samples {
                     sum = sum + shader(
                         scene,
-                        (x as f64) + rand(),
-                        (y as f64) + rand(),
-                        self.width as f64,
-                        self.height as f64,
+                        (x as Float) + rand(),
+                        (y as Float) + rand(),
+                        self.width as Float,
+                        self.height as Float,
+                        self.spectral,
                     );
                 }
-                (sum / (self.samples as f64)).color(GAMMA).rgb()
+                (sum / (self.samples as Float)).color(GAMMA).rgb()
             })
             .collect();
         print!("done.\nWriting render to buffer...");
@@ -94,7 +103,7 @@ impl Frame {
         print!("Saving to disc...");
         stdout().flush().unwrap();
         let ref mut file = File::create(path).unwrap();
-        ImageRgb8(self.buffer).save(file, format).unwrap();
+        self.buffer.write_to(file, format).unwrap();
         println!("done.");
     }
 
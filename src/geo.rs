@@ -1,20 +1,64 @@
-use util::rand;
+use util::{rand, gamma, next_float_up, next_float_down, Float, PI};
 use vec3::Vec3;
 use ray::Ray;
 use aabb::AABB;
 use material::Material;
 use std::cmp::Ordering::Equal;
 
+/// The distance the bounding boxes of flat primitives are padded by on
+/// either side, so the slab test in `AABB::hit` does not miss them
+const BBOX_PADDING: Float = 0.0001;
+
+/// The smallest cosine between a sampled direction and the surface of a rect
+/// or triangle light at which its solid angle density is defined, the density
+/// of more grazing directions is too large to weight samples with
+const MIN_COSINE: Float = 1e-6;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Differentials {
+    /// The change in the point across one pixel in x on screen
+    pub dpdx: Vec3,
+    /// The change in the point across one pixel in y on screen
+    pub dpdy: Vec3,
+    /// The change in u across one pixel in x on screen
+    pub dudx: Float,
+    /// The change in v across one pixel in x on screen
+    pub dvdx: Float,
+    /// The change in u across one pixel in y on screen
+    pub dudy: Float,
+    /// The change in v across one pixel in y on screen
+    pub dvdy: Float,
+}
+
 #[derive(Debug, Clone)]
 pub struct Intersection {
     /// The distance along the ray at which the intersection occured
-    pub t: f64,
+    pub t: Float,
     /// The intersecting ray
     pub ray: Ray,
     /// The point of intersection
     pub point: Vec3,
+    /// A conservative bound on the absolute floating-point error of `point`
+    pub error: Vec3,
     /// A normal off the intersection
     pub normal: Vec3,
+    /// The u surface coordinate of the intersection
+    pub u: Float,
+    /// The v surface coordinate of the intersection
+    pub v: Float,
+    /// The partial derivative of the point with respect to u, tangent to the
+    /// surface
+    pub dpdu: Vec3,
+    /// The partial derivative of the point with respect to v, tangent to the
+    /// surface
+    pub dpdv: Vec3,
+    /// The partial derivative of the normal with respect to u
+    pub dndu: Vec3,
+    /// The partial derivative of the normal with respect to v
+    pub dndv: Vec3,
+    /// The change in the intersection across a pixel on screen, computed by
+    /// `with_differentials` once the intersection is known to be shaded
+    pub differentials: Option<Differentials>,
     /// The material of the surface
     pub material: Material,
 }
@@ -25,18 +69,146 @@ impl Intersection {
     /// # Arguments
     /// * `t`      - the distance along the ray at which the intersection occured
     /// * `ray`    - the intersecting ray
+    /// * `point`  - the point of intersection
+    /// * `error`  - the absolute error bound on `point`
     /// * `normal` - a normal off the intersection
     ///
-    pub fn new(t: f64, ray: Ray, normal: Vec3, mat: Material) -> Self {
+    pub fn new(
+        t: Float,
+        ray: Ray,
+        point: Vec3,
+        error: Vec3,
+        normal: Vec3,
+        mat: Material,
+    ) -> Self {
+        let (dpdu, dpdv) = Vec3::basis(normal.unit());
         Intersection {
             t:        t,
             ray:      ray,
-            point:    ray.at(t),
+            point:    point,
+            error:    error,
             normal:   normal.unit(),
+            u:        0.0,
+            v:        0.0,
+            dpdu:     dpdu,
+            dpdv:     dpdv,
+            dndu:     Vec3::zeros(),
+            dndv:     Vec3::zeros(),
+            differentials: None,
             material: mat,
         }
     }
 
+    /// Sets the surface coordinates of the intersection and the tangents
+    /// along them
+    ///
+    /// # Arguments
+    /// * `u`    - the u surface coordinate
+    /// * `v`    - the v surface coordinate
+    /// * `dpdu` - the partial derivative of the point with respect to u
+    /// * `dpdv` - the partial derivative of the point with respect to v
+    ///
+    pub fn with_uv(self, u: Float, v: Float, dpdu: Vec3, dpdv: Vec3) -> Self {
+        Intersection { u, v, dpdu, dpdv, ..self }
+    }
+
+    /// Sets the partial derivatives of the normal along the surface
+    /// coordinates, which are zero for flat surfaces
+    pub fn with_normal_derivatives(self, dndu: Vec3, dndv: Vec3) -> Self {
+        Intersection { dndu, dndv, ..self }
+    }
+
+    /// Returns the width in surface coordinates of the footprint of a pixel
+    /// on screen at the intersection, zero if the ray carries no
+    /// differentials
+    pub fn uv_width(&self) -> Float {
+        self.differentials.map_or(0.0, |d| {
+            d.dudx.abs().max(d.dvdx.abs()).max(d.dudy.abs()).max(d.dvdy.abs())
+        })
+    }
+
+    /// Computes the change in the point and surface coordinates across a
+    /// pixel on screen, by intersecting the offset rays of the ray's
+    /// differential with the tangent plane of the intersection. This is
+    /// only worth doing for the closest hit, which is shaded.
+    pub fn with_differentials(self) -> Self {
+        let differential = match self.ray.differential {
+            Some(differential) => differential,
+            None               => return self,
+        };
+        let n = self.normal;
+        let d = Vec3::dot(n, self.point);
+        let offset = |origin: Vec3, dir: Vec3| {
+            let cosine = Vec3::dot(n, dir);
+            if cosine == 0.0 {
+                return None;
+            }
+            Some(origin + (d - Vec3::dot(n, origin)) / cosine * dir - self.point)
+        };
+        let (dpdx, dpdy) = match (
+            offset(differential.rx_origin, differential.rx_dir),
+            offset(differential.ry_origin, differential.ry_dir),
+        ) {
+            (Some(dpdx), Some(dpdy)) => (dpdx, dpdy),
+            _ => return self,
+        };
+        // Solve dp = du * dpdu + dv * dpdv in the two axes the normal is
+        // least aligned with, the system is overdetermined in three
+        let (a, b) = if n.x.abs() > n.y.abs() && n.x.abs() > n.z.abs() {
+            (1, 2)
+        } else if n.y.abs() > n.z.abs() {
+            (0, 2)
+        } else {
+            (0, 1)
+        };
+        let det = self.dpdu[a] * self.dpdv[b] - self.dpdv[a] * self.dpdu[b];
+        let solve = |dp: Vec3| {
+            if det == 0.0 {
+                return (0.0, 0.0);
+            }
+            let du = (self.dpdv[b] * dp[a] - self.dpdv[a] * dp[b]) / det;
+            let dv = (self.dpdu[a] * dp[b] - self.dpdu[b] * dp[a]) / det;
+            if du.is_finite() && dv.is_finite() { (du, dv) } else { (0.0, 0.0) }
+        };
+        let (dudx, dvdx) = solve(dpdx);
+        let (dudy, dvdy) = solve(dpdy);
+        let differentials = Differentials { dpdx, dpdy, dudx, dvdx, dudy, dvdy };
+        Intersection { differentials: Some(differentials), ..self }
+    }
+
+    /// Returns the intersection point offset off the surface towards the side
+    /// `dir` points to, so rays spawned from it can not re-intersect the
+    /// surface. The offset is just large enough to clear the error bounds of
+    /// the point, so it holds independent of scene scale.
+    ///
+    /// # Arguments
+    /// * `dir` - the direction of the spawned ray
+    ///
+    pub fn offset_point(&self, dir: Vec3) -> Vec3 {
+        let d = Vec3::dot(self.normal.abs(), self.error);
+        let offset = if Vec3::dot(dir, self.normal) > 0.0 {
+            d * self.normal
+        } else {
+            -d * self.normal
+        };
+        let p = self.point + offset;
+        // Round away from the surface to account for the addition above
+        let round = |v: Float, o: Float| {
+            if o > 0.0 {
+                next_float_up(v)
+            } else if o < 0.0 {
+                next_float_down(v)
+            } else {
+                v
+            }
+        };
+        Vec3::new(
+            round(p.x, offset.x),
+            round(p.y, offset.y),
+            round(p.z, offset.z),
+        )
+    }
+
     /// Returns the vector reflected by the ray off the normal
     pub fn reflected(&self) -> Vec3 {
         Vec3::reflect(self.ray.dir, self.normal)
@@ -60,9 +232,29 @@ pub enum Geo {
     Sphere {
         center0: Vec3,
         center1: Vec3,
-        radius: f64,
-        time0: f64,
-        time1: f64,
+        radius: Float,
+        time0: Float,
+        time1: Float,
+        material: Material,
+    },
+    /// A geometry representing an axis aligned rectangle, perpendicular to
+    /// `axis` at distance `k` and spanning [a0, a1] x [b0, b1] along the two
+    /// remaining axes. Rectangles are two sided.
+    Rect {
+        axis: u32,
+        a0: Float,
+        a1: Float,
+        b0: Float,
+        b1: Float,
+        k: Float,
+        material: Material,
+    },
+    /// A geometry representing a triangle, facing the side its vertices wind
+    /// counter-clockwise around
+    Triangle {
+        v0: Vec3,
+        v1: Vec3,
+        v2: Vec3,
         material: Material,
     },
 }
@@ -119,7 +311,7 @@ impl Geo {
     }
 
     /// Construct a new sphere
-    pub fn sphere(center: Vec3, radius: f64, mat: Material) -> Self {
+    pub fn sphere(center: Vec3, radius: Float, mat: Material) -> Self {
         Geo::Sphere {
             center0:  center,
             center1:  center,
@@ -131,7 +323,7 @@ impl Geo {
     }
 
     /// Construct a new moving sphere
-    pub fn moving_sphere(c0: Vec3, c1: Vec3, r: f64, t0: f64, t1: f64, mat: Material) -> Self {
+    pub fn moving_sphere(c0: Vec3, c1: Vec3, r: Float, t0: Float, t1: Float, mat: Material) -> Self {
         Geo::Sphere {
             center0:  c0,
             center1:  c1,
@@ -142,8 +334,46 @@ impl Geo {
         }
     }
 
+    /// Construct a new axis aligned rectangle
+    pub fn rect(axis: u32, a0: Float, a1: Float, b0: Float, b1: Float, k: Float, mat: Material) -> Self {
+        Geo::Rect {
+            axis:     axis,
+            a0:       a0,
+            a1:       a1,
+            b0:       b0,
+            b1:       b1,
+            k:        k,
+            material: mat,
+        }
+    }
+
+    /// Construct a new rectangle in the xy plane
+    pub fn xy_rect(x0: Float, x1: Float, y0: Float, y1: Float, k: Float, mat: Material) -> Self {
+        Geo::rect(2, x0, x1, y0, y1, k, mat)
+    }
+
+    /// Construct a new rectangle in the xz plane
+    pub fn xz_rect(x0: Float, x1: Float, z0: Float, z1: Float, k: Float, mat: Material) -> Self {
+        Geo::rect(1, x0, x1, z0, z1, k, mat)
+    }
+
+    /// Construct a new rectangle in the yz plane
+    pub fn yz_rect(y0: Float, y1: Float, z0: Float, z1: Float, k: Float, mat: Material) -> Self {
+        Geo::rect(0, y0, y1, z0, z1, k, mat)
+    }
+
+    /// Construct a new triangle
+    pub fn triangle(v0: Vec3, v1: Vec3, v2: Vec3, mat: Material) -> Self {
+        Geo::Triangle {
+            v0:       v0,
+            v1:       v1,
+            v2:       v2,
+            material: mat,
+        }
+    }
+
     /// Find the center of a sphere at a given time
-    pub fn center(&self, time: f64) -> Vec3 {
+    pub fn center(&self, time: Float) -> Vec3 {
         match self {
             &Geo::Sphere { center0, center1, time0, time1, .. } => center0
                 + ((time - time0) / (time1 - time0))
@@ -162,7 +392,7 @@ impl Geo {
     ///`* `min` - the minimum distance along the ray
     /// * `max` - the maximum distance along the ray
     ///
-    pub fn intersects(&self, ray: Ray, min: f64, max: f64) -> Option<Intersection> {
+    pub fn intersects(&self, ray: Ray, min: Float, max: Float) -> Option<Intersection> {
         match self {
             // Handle intersection for a list
             &Geo::List { ref children } => {
@@ -202,28 +432,254 @@ impl Geo {
             // Handle intersection for a sphere
             &Geo::Sphere { radius, ref material, .. } => {
                 let center = self.center(ray.time);
-                let intersect = |t| Some(Intersection::new(
-                    t, ray,
-                    (ray.at(t) - center) / radius,
-                    material.clone()
-                ));
+                let intersect = |t| {
+                    // Reproject the point onto the surface to reduce its
+                    // error, the remaining error comes from the reprojection
+                    // and the translation by the center
+                    let local = ray.at(t) - center;
+                    let local = local * radius.abs() / local.len();
+                    let point = center + local;
+                    let error = gamma(5) * local.abs() + gamma(1) * point.abs();
+                    // Spherical coordinates, u around the y axis and v from
+                    // the bottom pole to the top
+                    let r = radius.abs();
+                    let phi = (-local.z).atan2(local.x);
+                    let phi = if phi < 0.0 { phi + 2.0 * PI } else { phi };
+                    let theta = (local.y / r).max(-1.0).min(1.0).acos();
+                    let dpdu = 2.0 * PI * Vec3::new(local.z, 0.0, -local.x);
+                    let dpdv = -PI * Vec3::new(
+                        local.y * phi.cos(),
+                        -r * theta.sin(),
+                        -local.y * phi.sin(),
+                    );
+                    Some(Intersection::new(
+                        t, ray, point, error,
+                        local / radius,
+                        material.clone()
+                    )
+                        .with_uv(phi / (2.0 * PI), 1.0 - theta / PI, dpdu, dpdv)
+                        .with_normal_derivatives(dpdu / radius, dpdv / radius))
+                };
                 let oc = ray.origin - center;
                 let a = Vec3::dot(ray.dir, ray.dir);
                 let b = Vec3::dot(oc, ray.dir);
                 let c = Vec3::dot(oc, oc) - radius * radius;
                 let d = b * b - a * c;
                 if d > 0.0 {
+                    // Conservative bound on the error of either root, roots
+                    // within it of min are rejected as self-intersections
+                    let error = gamma(7) * (b.abs() + d.sqrt()) / a;
                     let t = (-b - d.sqrt()) / a;
-                    if min < t && t < max {
+                    if min < t - error && t < max {
                         return intersect(t);
                     }
                     let t = (-b + d.sqrt()) / a;
-                    if min < t && t < max {
+                    if min < t - error && t < max {
                         return intersect(t);
                     }
                 }
                 None
-            }
+            },
+            // Handle intersection for a rectangle
+            &Geo::Rect { axis, a0, a1, b0, b1, k, ref material } => {
+                let (ia, ib) = match axis { 0 => (1, 2), 1 => (0, 2), _ => (0, 1) };
+                // A ray parallel to the plane never hits it, and one lying in
+                // it would give a NaN distance
+                if ray.dir[axis] == 0.0 {
+                    return None;
+                }
+                let t = (k - ray.origin[axis]) / ray.dir[axis];
+                // Conservative bound on the error of the distance, hits within
+                // it of min are rejected as self-intersections
+                let error = gamma(2) * (k.abs() + ray.origin[axis].abs()) / ray.dir[axis].abs();
+                if t - error <= min || t >= max {
+                    return None;
+                }
+                let p = ray.at(t);
+                let (a, b) = (p[ia], p[ib]);
+                if a < a0 || a > a1 || b < b0 || b > b1 {
+                    return None;
+                }
+                // The point lies exactly on the plane, only the in plane
+                // coordinates carry error from evaluating the ray
+                let point  = Vec3::from_axes(axis, k, a, b);
+                let error  = gamma(3) * Vec3::from_axes(axis, 0.0, a.abs(), b.abs());
+                let normal = Vec3::from_axes(axis, 1.0, 0.0, 0.0);
+                // Face the normal towards the incoming ray
+                let normal = if ray.dir[axis] > 0.0 { -normal } else { normal };
+                let dpdu = Vec3::from_axes(axis, 0.0, a1 - a0, 0.0);
+                let dpdv = Vec3::from_axes(axis, 0.0, 0.0, b1 - b0);
+                Some(Intersection::new(t, ray, point, error, normal, material.clone())
+                    .with_uv((a - a0) / (a1 - a0), (b - b0) / (b1 - b0), dpdu, dpdv))
+            },
+            // Handle intersection for a triangle
+            &Geo::Triangle { v0, v1, v2, ref material } => {
+                let e1 = v1 - v0;
+                let e2 = v2 - v0;
+                let p  = Vec3::cross(ray.dir, e2);
+                let det = Vec3::dot(e1, p);
+                // Only a degenerate or edge on triangle is rejected here, the
+                // error bounds on the hit handle nearly edge on ones
+                let inv_det = 1.0 / det;
+                if det == 0.0 || !inv_det.is_finite() {
+                    return None;
+                }
+                let s  = ray.origin - v0;
+                let b1 = Vec3::dot(s, p) * inv_det;
+                if b1 < 0.0 || b1 > 1.0 {
+                    return None;
+                }
+                let q  = Vec3::cross(s, e1);
+                let b2 = Vec3::dot(ray.dir, q) * inv_det;
+                if b2 < 0.0 || b1 + b2 > 1.0 {
+                    return None;
+                }
+                let t = Vec3::dot(e2, q) * inv_det;
+                // Conservative bound on the error of the distance, from the
+                // magnitudes of the terms summed by the cross and dot
+                // products of the differences of the origin and vertices,
+                // hits within it of min are rejected as self-intersections
+                let terms = |u: Vec3, v: Vec3| Vec3::new(
+                    u.y * v.z + u.z * v.y,
+                    u.z * v.x + u.x * v.z,
+                    u.x * v.y + u.y * v.x,
+                );
+                let (m0, m1, m2) = (v0.abs(), v1.abs() + v0.abs(), v2.abs() + v0.abs());
+                let error = gamma(7) * inv_det.abs() * (
+                    Vec3::dot(m2, terms(ray.origin.abs() + m0, m1))
+                        + t.abs() * Vec3::dot(m1, terms(ray.dir.abs(), m2))
+                );
+                if t - error <= min || t >= max {
+                    return None;
+                }
+                // Interpolate the point from the barycentric coordinates,
+                // which bounds its error by the vertex magnitudes
+                let b0    = 1.0 - b1 - b2;
+                let point = b0 * v0 + b1 * v1 + b2 * v2;
+                let error = gamma(7) * ((b0 * v0).abs() + (b1 * v1).abs() + (b2 * v2).abs());
+                let normal = Vec3::cross(e1, e2);
+                // Vertices take the default coordinates (0, 0), (1, 0) and
+                // (0, 1), so the barycentrics are the surface coordinates
+                Some(Intersection::new(t, ray, point, error, normal, material.clone())
+                    .with_uv(b1, b2, e1, e2))
+            },
+        }
+    }
+
+    /// Return the material of a primitive
+    pub fn material(&self) -> &Material {
+        match self {
+            &Geo::Sphere { ref material, .. }   => material,
+            &Geo::Rect { ref material, .. }     => material,
+            &Geo::Triangle { ref material, .. } => material,
+            other => panic!("{:?} does not have a material!", other),
+        }
+    }
+
+    /// Collects every primitive with an emissive material
+    pub fn emitters(&self) -> Vec<Geo> {
+        match self {
+            &Geo::List { ref children } => children
+                .iter()
+                .flat_map(|geo| geo.emitters())
+                .collect(),
+            &Geo::BVH_node { ref left, ref right, .. } => {
+                let mut emitters = left.emitters();
+                emitters.extend(right.emitters());
+                emitters
+            },
+            primitive => if primitive.material().is_emissive() {
+                vec![primitive.clone()]
+            } else {
+                Vec::new()
+            },
+        }
+    }
+
+    /// Samples a direction from origin towards a primitive, such that the
+    /// direction has the density returned by `pdf_value`.
+    ///
+    /// # Arguments
+    /// * `origin` - the point being sampled from
+    /// * `time`   - the time the primitive is sampled at
+    ///
+    pub fn random_direction(&self, origin: Vec3, time: Float) -> Vec3 {
+        match self {
+            // Sample the cone of directions subtended by the sphere
+            &Geo::Sphere { radius, .. } => {
+                let d = self.center(time) - origin;
+                let dist2 = d.mag();
+                let r2 = radius * radius;
+                if dist2 <= r2 {
+                    return Vec3::rand_unit();
+                }
+                let cos_max = (1.0 - r2 / dist2).sqrt();
+                let z   = 1.0 + rand() * (cos_max - 1.0);
+                let phi = 2.0 * PI * rand();
+                let sin = (1.0 - z * z).max(0.0).sqrt();
+                let w   = d.unit();
+                let (u, v) = Vec3::basis(w);
+                sin * phi.cos() * u + sin * phi.sin() * v + z * w
+            },
+            // Sample a point uniformly across the rectangle's area
+            &Geo::Rect { axis, a0, a1, b0, b1, k, .. } => {
+                let a = a0 + rand() * (a1 - a0);
+                let b = b0 + rand() * (b1 - b0);
+                Vec3::from_axes(axis, k, a, b) - origin
+            },
+            // Sample a point uniformly across the triangle's area
+            &Geo::Triangle { v0, v1, v2, .. } => {
+                let su = rand().sqrt();
+                let b1 = 1.0 - su;
+                let b2 = rand() * su;
+                (1.0 - b1 - b2) * v0 + b1 * v1 + b2 * v2 - origin
+            },
+            other => panic!("{:?} can not be sampled!", other),
+        }
+    }
+
+    /// Returns the solid angle density with which `random_direction` samples
+    /// the direction of a ray from its origin.
+    ///
+    /// # Arguments
+    /// * `ray` - the ray from the sampled point
+    ///
+    pub fn pdf_value(&self, ray: Ray) -> Float {
+        let i = match self.intersects(ray, 0.0, Float::MAX) {
+            Some(i) => i,
+            None    => return 0.0,
+        };
+        match self {
+            &Geo::Sphere { radius, .. } => {
+                let dist2 = (self.center(ray.time) - ray.origin).mag();
+                let r2 = radius * radius;
+                if dist2 <= r2 {
+                    return 1.0 / (4.0 * PI);
+                }
+                let cos_max = (1.0 - r2 / dist2).sqrt();
+                1.0 / (2.0 * PI * (1.0 - cos_max))
+            },
+            // Convert the area density into a solid angle density
+            &Geo::Rect { .. } | &Geo::Triangle { .. } => {
+                let cosine = Vec3::dot(ray.dir, i.normal).abs();
+                if cosine < MIN_COSINE {
+                    return 0.0;
+                }
+                i.t * i.t / (cosine * self.area())
+            },
+            other => panic!("{:?} can not be sampled!", other),
+        }
+    }
+
+    /// Returns the surface area of a primitive
+    pub fn area(&self) -> Float {
+        match self {
+            &Geo::Sphere { radius, .. } => 4.0 * PI * radius * radius,
+            &Geo::Rect { a0, a1, b0, b1, .. } => (a1 - a0) * (b1 - b0),
+            &Geo::Triangle { v0, v1, v2, .. } => {
+                0.5 * Vec3::cross(v1 - v0, v2 - v0).len()
+            },
+            other => panic!("{:?} does not have an area!", other),
         }
     }
 
@@ -247,7 +703,117 @@ impl Geo {
                 let box2    = AABB::new(center1 - vradius, center1 + vradius);
                 AABB::bound_boxes(box1, box2)
             },
+            // Determine the bounding box for a rectangle, padded so it is
+            // not infinitely thin
+            &Geo::Rect { axis, a0, a1, b0, b1, k, .. } => AABB::new(
+                Vec3::from_axes(axis, k - BBOX_PADDING, a0, b0),
+                Vec3::from_axes(axis, k + BBOX_PADDING, a1, b1),
+            ),
+            // Determine the bounding box for a triangle, padded so it is
+            // not infinitely thin when axis aligned
+            &Geo::Triangle { v0, v1, v2, .. } => {
+                let pad = Vec3::ones() * BBOX_PADDING;
+                let vbox = |v: Vec3| AABB::new(v - pad, v + pad);
+                AABB::bound_boxes(
+                    vbox(v0),
+                    AABB::bound_boxes(vbox(v1), vbox(v2)),
+                )
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use texture::Texture;
+
+    /// Returns an intersection at a point with the given error bounds and
+    /// normal, hit by a ray arriving against the normal
+    fn hit(point: Vec3, error: Vec3, normal: Vec3) -> Intersection {
+        let material = Material::lambertian(Texture::solid(Vec3::ones()));
+        let ray = Ray::new(point + normal, -normal, 0.0);
+        Intersection::new(1.0, ray, point, error, normal, material)
+    }
+
+    #[test]
+    fn offset_point_clears_the_error_bounds() {
+        let point = Vec3::new(0.3, -0.7, 2.5);
+        let error = Vec3::new(1e-5, 2e-5, 3e-5);
+        let i = hit(point, error, Vec3::new(0.0, 0.0, 1.0));
+        let above = i.offset_point(Vec3::new(0.0, 0.0, 1.0));
+        let below = i.offset_point(Vec3::new(0.0, 0.0, -1.0));
+        assert!(above.z > point.z + error.z);
+        assert!(below.z < point.z - error.z);
+        // The point only moves along the normal
+        assert_eq!((above.x, above.y), (point.x, point.y));
+        assert_eq!((below.x, below.y), (point.x, point.y));
+    }
+
+    #[test]
+    fn offset_point_follows_the_side_of_the_spawned_ray() {
+        let normal = Vec3::new(1.0, 2.0, -2.0) / 3.0;
+        let point = Vec3::new(-4.0, 1.0, 0.5);
+        let error = Vec3::ones() * 1e-4;
+        let i = hit(point, error, normal);
+        let u = Vec3::new(2.0, -1.0, 0.0).unit();
+        // Grazing directions on either side of the surface
+        let out = i.offset_point(u + 0.01 * normal);
+        let into = i.offset_point(u - 0.01 * normal);
+        let d = Vec3::dot(normal.abs(), error);
+        assert!(Vec3::dot(out - point, normal) > 0.99 * d);
+        assert!(Vec3::dot(into - point, normal) < -0.99 * d);
+    }
 
+    #[test]
+    fn offset_point_scales_with_the_scene() {
+        for &scale in &[1e-3, 1.0, 1e3, 1e5] {
+            let point = Vec3::new(1.0, -2.0, 3.0) * scale;
+            let error = gamma(7) * point.abs();
+            let i = hit(point, error, Vec3::new(0.0, 1.0, 0.0));
+            let offset = i.offset_point(Vec3::new(0.0, 1.0, 0.0)).y - point.y;
+            assert!(offset > error.y);
+            assert!(offset < 1e-3 * scale);
         }
     }
+
+    #[test]
+    fn rect_rejects_hits_within_the_error_of_the_origin() {
+        let material = Material::lambertian(Texture::solid(Vec3::ones()));
+        let rect = Geo::xy_rect(-2000.0, 2000.0, -2000.0, 2000.0, 1000.0, material);
+        // An origin a rounding step off the plane, heading back into it
+        let origin = Vec3::new(1500.0, -1500.0, next_float_up(1000.0));
+        let ray = Ray::new(origin, Vec3::new(0.3, 0.2, -1.0), 0.0);
+        assert!(rect.intersects(ray, 0.0, Float::MAX).is_none());
+        let ray = Ray::new(Vec3::new(1500.0, -1500.0, 1010.0), Vec3::new(0.3, 0.2, -1.0), 0.0);
+        assert!(rect.intersects(ray, 0.0, Float::MAX).is_some());
+    }
+
+    #[test]
+    fn triangle_rejects_hits_within_the_error_of_the_origin() {
+        let material = Material::lambertian(Texture::solid(Vec3::ones()));
+        let triangle = Geo::triangle(
+            Vec3::new(-2000.0, -2000.0, 1000.0),
+            Vec3::new(2000.0, -2000.0, 1000.0),
+            Vec3::new(-2000.0, 2000.0, 1000.0),
+            material,
+        );
+        // An origin a rounding step off the plane, heading back into it
+        let origin = Vec3::new(-1000.0, -500.0, next_float_up(1000.0));
+        let ray = Ray::new(origin, Vec3::new(0.1, 0.2, -1.0), 0.0);
+        assert!(triangle.intersects(ray, 0.0, Float::MAX).is_none());
+        let ray = Ray::new(Vec3::new(-1000.0, -500.0, 1010.0), Vec3::new(0.1, 0.2, -1.0), 0.0);
+        assert!(triangle.intersects(ray, 0.0, Float::MAX).is_some());
+    }
+
+    #[test]
+    fn rect_density_vanishes_at_grazing_directions() {
+        let material = Material::lambertian(Texture::solid(Vec3::ones()));
+        let rect = Geo::xy_rect(-1.0, 1.0, -1.0, 1.0, 0.0, material);
+        let head_on = Ray::new(Vec3::new(0.0, 0.0, 2.0), Vec3::new(0.0, 0.0, -1.0), 0.0);
+        assert!((rect.pdf_value(head_on) - 1.0).abs() < 1e-5);
+        let grazing = Ray::new(Vec3::new(-2.0, 0.5, 1e-8), Vec3::new(1.0, 0.0, -1e-8), 0.0);
+        assert!(rect.intersects(grazing, 0.0, Float::MAX).is_some());
+        assert_eq!(rect.pdf_value(grazing), 0.0);
+    }
 }
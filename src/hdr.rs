@@ -0,0 +1,113 @@
+use util::Float;
+use vec3::Vec3;
+use std::path::Path;
+use std::fs::File;
+use std::io::Read;
+
+/// Loads a Radiance RGBE (.hdr) image, returning its width, height and
+/// pixels in rows from the top left. Only the standard `-Y H +X W`
+/// orientation is supported.
+pub fn load<P: AsRef<Path>>(path: P) -> (usize, usize, Vec<Vec3>) {
+    let mut bytes = Vec::new();
+    File::open(path)
+        .expect("Failed to open HDR file!")
+        .read_to_end(&mut bytes)
+        .expect("Failed to read HDR file!");
+    let mut pos = 0;
+    // Read the header up to the blank line separating it from the resolution
+    let mut line = read_line(&bytes, &mut pos);
+    if !line.starts_with("#?") {
+        panic!("Not a Radiance HDR file!");
+    }
+    while !line.is_empty() {
+        if line.starts_with("FORMAT=") && line != "FORMAT=32-bit_rle_rgbe" {
+            panic!("Unsupported HDR format {}!", &line[7..]);
+        }
+        line = read_line(&bytes, &mut pos);
+    }
+    let resolution = read_line(&bytes, &mut pos);
+    let fields: Vec<&str> = resolution.split_whitespace().collect();
+    let (height, width) = match fields.as_slice() {
+        &["-Y", h, "+X", w] => (
+            h.parse().expect("Invalid HDR height!"),
+            w.parse().expect("Invalid HDR width!"),
+        ),
+        _ => panic!("Unsupported HDR resolution {}!", resolution),
+    };
+    let mut pixels = Vec::with_capacity(width * height);
+    let mut scanline = vec![[0u8; 4]; width];
+    for _ in 0..height {
+        read_scanline(&bytes, &mut pos, &mut scanline);
+        pixels.extend(scanline.iter().map(|rgbe| rgbe_to_vec3(*rgbe)));
+    }
+    (width, height, pixels)
+}
+
+/// Reads a newline terminated line of text
+fn read_line(bytes: &[u8], pos: &mut usize) -> String {
+    let start = *pos;
+    while *pos < bytes.len() && bytes[*pos] != b'\n' {
+        *pos += 1;
+    }
+    let line = String::from_utf8_lossy(&bytes[start..*pos]).trim().to_string();
+    *pos += 1;
+    line
+}
+
+/// Reads the next byte, panicking at the end of the file
+fn next(bytes: &[u8], pos: &mut usize) -> u8 {
+    let byte = *bytes.get(*pos).expect("Unexpected end of HDR file!");
+    *pos += 1;
+    byte
+}
+
+/// Reads a scanline, either flat or run length encoded per channel
+fn read_scanline(bytes: &[u8], pos: &mut usize, scanline: &mut [[u8; 4]]) {
+    let width = scanline.len();
+    let rle = width >= 8 && width < 0x8000
+        && bytes.get(*pos) == Some(&2)
+        && bytes.get(*pos + 1) == Some(&2)
+        && bytes.get(*pos + 2).map_or(false, |b| b & 0x80 == 0);
+    if !rle {
+        for pixel in scanline.iter_mut() {
+            for c in 0..4 {
+                pixel[c] = next(bytes, pos);
+            }
+        }
+        return;
+    }
+    *pos += 4;
+    for c in 0..4 {
+        let mut x = 0;
+        while x < width {
+            let count = next(bytes, pos) as usize;
+            let run = if count > 128 { count - 128 } else { count };
+            if run == 0 || x + run > width {
+                panic!("Corrupt HDR scanline!");
+            }
+            if count > 128 {
+                // A run of a single value
+                let value = next(bytes, pos);
+                for _ in 0..run {
+                    scanline[x][c] = value;
+                    x += 1;
+                }
+            } else {
+                // A run of literal values
+                for _ in 0..run {
+                    scanline[x][c] = next(bytes, pos);
+                    x += 1;
+                }
+            }
+        }
+    }
+}
+
+/// Converts a shared exponent pixel to a vector
+fn rgbe_to_vec3(rgbe: [u8; 4]) -> Vec3 {
+    if rgbe[3] == 0 {
+        return Vec3::zeros();
+    }
+    let f = (2.0 as Float).powi(rgbe[3] as i32 - (128 + 8));
+    Vec3::new(rgbe[0] as Float, rgbe[1] as Float, rgbe[2] as Float) * f
+}
@@ -0,0 +1,149 @@
+use util::{Float, PI};
+use vec3::Vec3;
+use std::path::Path;
+use std::fs::File;
+use std::io::Read;
+
+#[derive(Debug, Clone)]
+pub struct IesProfile {
+    /// The vertical angles in degrees, 0 pointing down the light's axis
+    vertical: Vec<Float>,
+    /// The horizontal angles in degrees around the light's axis
+    horizontal: Vec<Float>,
+    /// The candela values, one row of vertical angles per horizontal angle
+    candela: Vec<Float>,
+    /// The average candela value over the sphere of directions
+    average: Float,
+}
+
+impl IesProfile {
+    /// Loads an IES LM-63 photometric profile. Only type C photometry is
+    /// supported, with tilt data skipped.
+    pub fn load<P: AsRef<Path>>(path: P) -> Self {
+        let mut contents = String::new();
+        File::open(path)
+            .expect("Failed to open IES file!")
+            .read_to_string(&mut contents)
+            .expect("Failed to read IES file!");
+        // Skip the keyword header up to and including the tilt line
+        let mut lines = contents.lines();
+        let tilt = loop {
+            match lines.next() {
+                Some(line) if line.trim().starts_with("TILT=") => break line.trim()[5..].to_string(),
+                Some(_) => (),
+                None    => panic!("IES file has no TILT line!"),
+            }
+        };
+        let rest: Vec<&str> = lines.collect();
+        let mut numbers = rest
+            .iter()
+            .flat_map(|line| line.split(|c: char| c.is_whitespace() || c == ','))
+            .filter(|token| !token.is_empty())
+            .map(|token| token.parse::<Float>().expect("Invalid number in IES file!"));
+        let mut next = || numbers.next().expect("Unexpected end of IES file!");
+        if tilt == "INCLUDE" {
+            // Lamp to luminaire geometry followed by angle and factor pairs
+            let _geometry = next();
+            let count = next() as usize;
+            for _ in 0..(2 * count) {
+                next();
+            }
+        }
+        let _lamps   = next();
+        let _lumens  = next();
+        let multiplier = next();
+        let n_vertical   = next() as usize;
+        let n_horizontal = next() as usize;
+        let photometric_type = next() as u32;
+        if photometric_type != 1 {
+            panic!("Unsupported IES photometric type {}!", photometric_type);
+        }
+        // Units, width, length, height, ballast factor, future use, watts
+        for _ in 0..7 {
+            next();
+        }
+        let vertical:   Vec<Float> = (0..n_vertical).map(|_| next()).collect();
+        let horizontal: Vec<Float> = (0..n_horizontal).map(|_| next()).collect();
+        let candela: Vec<Float> = (0..(n_vertical * n_horizontal))
+            .map(|_| next() * multiplier)
+            .collect();
+        let mut profile = IesProfile { vertical, horizontal, candela, average: 0.0 };
+        profile.average = profile.integrate() / (4.0 * PI);
+        profile
+    }
+
+    /// The average candela value over the sphere of directions
+    pub fn average(&self) -> Float {
+        self.average
+    }
+
+    /// Returns the candela value emitted in a direction
+    ///
+    /// # Arguments
+    /// * `dir`   - the direction the light leaves in
+    /// * `axis`  - the direction of the light's zero vertical angle
+    ///
+    pub fn value(&self, dir: Vec3, axis: Vec3) -> Float {
+        let dir = dir.unit();
+        let (u, v) = Vec3::basis(axis);
+        let theta = Vec3::dot(dir, axis).max(-1.0).min(1.0).acos().to_degrees();
+        let phi   = Vec3::dot(dir, v).atan2(Vec3::dot(dir, u)).to_degrees();
+        self.candela(theta, phi)
+    }
+
+    /// Returns the interpolated candela value at the given angles in degrees
+    fn candela(&self, theta: Float, phi: Float) -> Float {
+        let phi = phi - 360.0 * (phi / 360.0).floor();
+        // Fold the horizontal angle according to the profile's symmetry
+        let last = *self.horizontal.last().unwrap();
+        let phi = if self.horizontal.len() == 1 {
+            0.0
+        } else if last <= 90.0 {
+            let phi = if phi > 180.0 { 360.0 - phi } else { phi };
+            if phi > 90.0 { 180.0 - phi } else { phi }
+        } else if last <= 180.0 {
+            if phi > 180.0 { 360.0 - phi } else { phi }
+        } else {
+            phi
+        };
+        let (h0, h1, ht) = bracket(&self.horizontal, phi);
+        let (v0, v1, vt) = bracket(&self.vertical, theta);
+        let n = self.vertical.len();
+        let at = |h: usize, v: usize| self.candela[h * n + v];
+        let c0 = at(h0, v0) * (1.0 - vt) + at(h0, v1) * vt;
+        let c1 = at(h1, v0) * (1.0 - vt) + at(h1, v1) * vt;
+        c0 * (1.0 - ht) + c1 * ht
+    }
+
+    /// Integrates the candela values over the sphere of directions
+    fn integrate(&self) -> Float {
+        let (n_theta, n_phi) = (64, 128);
+        let mut sum = 0.0;
+        for i in 0..n_theta {
+            let theta = 180.0 * ((i as Float) + 0.5) / (n_theta as Float);
+            for j in 0..n_phi {
+                let phi = 360.0 * ((j as Float) + 0.5) / (n_phi as Float);
+                sum += self.candela(theta, phi) * theta.to_radians().sin();
+            }
+        }
+        sum * (PI / n_theta as Float) * (2.0 * PI / n_phi as Float)
+    }
+}
+
+/// Finds the pair of indices of sorted angles around the given angle, and
+/// the interpolation weight between them, clamping outside the range
+fn bracket(angles: &[Float], angle: Float) -> (usize, usize, Float) {
+    let n = angles.len();
+    if n == 1 || angle <= angles[0] {
+        return (0, 0, 0.0);
+    }
+    if angle >= angles[n - 1] {
+        return (n - 1, n - 1, 0.0);
+    }
+    let mut i = 0;
+    while angles[i + 1] < angle {
+        i += 1;
+    }
+    let t = (angle - angles[i]) / (angles[i + 1] - angles[i]);
+    (i, i + 1, t)
+}
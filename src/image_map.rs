@@ -0,0 +1,280 @@
+use util::Float;
+use vec3::Vec3;
+use std::path::Path;
+use image;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Filter {
+    /// Takes the value of the nearest pixel
+    Nearest,
+    /// Interpolates between the four nearest pixels
+    Bilinear,
+    /// Interpolates bilinearly between the two MIP levels whose pixels best
+    /// match the footprint of the lookup
+    Trilinear,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Wrap {
+    /// Tiles the image beyond its edges
+    Repeat,
+    /// Extends the edge pixels of the image beyond its edges
+    Clamp,
+    /// Tiles the image beyond its edges, flipping every other tile
+    Mirror,
+}
+
+impl Wrap {
+    /// Maps a pixel index to one inside an image of the given size
+    fn apply(&self, i: isize, size: usize) -> usize {
+        let size = size as isize;
+        match self {
+            &Wrap::Repeat => i.rem_euclid(size) as usize,
+            &Wrap::Clamp  => i.max(0).min(size - 1) as usize,
+            &Wrap::Mirror => {
+                let i = i.rem_euclid(2 * size);
+                (if i < size { i } else { 2 * size - 1 - i }) as usize
+            },
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Level {
+    /// The level width in pixels
+    width: usize,
+    /// The level height in pixels
+    height: usize,
+    /// The level pixels in rows from the top left, with channels in [0, 1]
+    pixels: Vec<Vec3>,
+}
+
+impl Level {
+    /// Returns the pixel at the given column and row, wrapped into the level
+    fn pixel(&self, x: isize, y: isize, wrap: Wrap) -> Vec3 {
+        self.pixels[wrap.apply(y, self.height) * self.width + wrap.apply(x, self.width)]
+    }
+
+    /// Returns the level at half the resolution, each pixel box filtering
+    /// the pixels beneath it so the whole level is covered even when its
+    /// size is odd
+    fn downsample(&self) -> Level {
+        let width = (self.width / 2).max(1);
+        let height = (self.height / 2).max(1);
+        let mut pixels = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                let mut sum = Vec3::zeros();
+                for &(sy, wy) in &taps(self.height, y) {
+                    for &(sx, wx) in &taps(self.width, x) {
+                        sum = sum + self.pixels[sy * self.width + sx] * (wx * wy);
+                    }
+                }
+                pixels.push(sum);
+            }
+        }
+        Level { width, height, pixels }
+    }
+
+    /// Returns the nearest or bilinearly filtered value at pixel coordinates
+    fn lookup(&self, x: Float, y: Float, bilinear: bool, wrap: Wrap) -> Vec3 {
+        if !bilinear {
+            return self.pixel(x.floor() as isize, y.floor() as isize, wrap);
+        }
+        let (x, y) = (x - 0.5, y - 0.5);
+        let (x0, y0) = (x.floor(), y.floor());
+        let (dx, dy) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as isize, y0 as isize);
+        (1.0 - dx) * (1.0 - dy) * self.pixel(x0, y0, wrap)
+            + dx * (1.0 - dy) * self.pixel(x0 + 1, y0, wrap)
+            + (1.0 - dx) * dy * self.pixel(x0, y0 + 1, wrap)
+            + dx * dy * self.pixel(x0 + 1, y0 + 1, wrap)
+    }
+}
+
+/// Returns the pixels along one axis of a level, with their weights, which
+/// are box filtered into a pixel of the level at half its size. Even sizes
+/// average pairs of pixels while odd sizes of 2n + 1 spread each pixel over
+/// a three pixel window, so every pixel contributes equally.
+///
+/// # Arguments
+/// * `size` - the size of the level along the axis
+/// * `i`    - the pixel index along the axis in the downsampled level
+///
+fn taps(size: usize, i: usize) -> Vec<(usize, Float)> {
+    if size == 1 {
+        return vec![(0, 1.0)];
+    }
+    if size % 2 == 0 {
+        return vec![(2 * i, 0.5), (2 * i + 1, 0.5)];
+    }
+    let n = (size / 2) as Float;
+    let total = 2.0 * n + 1.0;
+    let k = i as Float;
+    vec![
+        (2 * i, (n - k) / total),
+        (2 * i + 1, n / total),
+        (2 * i + 2, (k + 1.0) / total),
+    ]
+}
+
+#[derive(Debug)]
+pub struct ImageMap {
+    /// The MIP pyramid of the image, from the full resolution image down to
+    /// a single pixel
+    levels: Vec<Level>,
+    /// The average color of the image
+    average: Vec3,
+}
+
+impl ImageMap {
+    /// Loads an 8 bit image such as a .png or .jpg, mapping its channels to
+    /// [0, 1] as stored without any color space conversion, as for normal
+    /// maps and other data
+    pub fn load<P: AsRef<Path>>(path: P) -> Self {
+        ImageMap::decode(path, |c| (c as Float) / 255.0)
+    }
+
+    /// Loads an 8 bit sRGB encoded image such as a .png or .jpg, converting
+    /// its colors to linear
+    pub fn load_srgb<P: AsRef<Path>>(path: P) -> Self {
+        ImageMap::decode(path, srgb_to_linear)
+    }
+
+    /// Loads an 8 bit image, decoding each channel with the given function
+    fn decode<P: AsRef<Path>, F: Fn(u8) -> Float>(path: P, channel: F) -> Self {
+        let image = image::open(path).expect("Failed to open image!").to_rgb8();
+        let (width, height) = image.dimensions();
+        let pixels: Vec<Vec3> = image.pixels()
+            .map(|p| Vec3::new(channel(p.0[0]), channel(p.0[1]), channel(p.0[2])))
+            .collect();
+        let sum = pixels.iter().fold(Vec3::zeros(), |sum, &p| sum + p);
+        let average = sum / (pixels.len().max(1) as Float);
+        let mut levels = vec![Level {
+            width:  width as usize,
+            height: height as usize,
+            pixels: pixels,
+        }];
+        while levels[levels.len() - 1].width > 1 || levels[levels.len() - 1].height > 1 {
+            let next = levels[levels.len() - 1].downsample();
+            levels.push(next);
+        }
+        ImageMap { levels, average }
+    }
+
+    /// Returns the average color of the image
+    pub fn average(&self) -> Vec3 {
+        self.average
+    }
+
+    /// Returns the filtered value at surface coordinates (u, v), with v
+    /// running from the bottom of the image to the top
+    ///
+    /// # Arguments
+    /// * `u`      - the u surface coordinate
+    /// * `v`      - the v surface coordinate
+    /// * `width`  - the width of the lookup's footprint in surface
+    ///              coordinates, which selects the MIP level for trilinear
+    ///              filtering
+    /// * `filter` - how pixels are filtered
+    /// * `wrap`   - how the image extends beyond [0, 1]
+    ///
+    pub fn lookup(&self, u: Float, v: Float, width: Float, filter: Filter, wrap: Wrap) -> Vec3 {
+        let at = |level: usize, bilinear: bool| {
+            let level = &self.levels[level];
+            let x = u * (level.width as Float);
+            let y = (1.0 - v) * (level.height as Float);
+            level.lookup(x, y, bilinear, wrap)
+        };
+        match filter {
+            Filter::Nearest  => at(0, false),
+            Filter::Bilinear => at(0, true),
+            Filter::Trilinear => {
+                // The level where the footprint spans a single pixel
+                let size = self.levels[0].width.max(self.levels[0].height) as Float;
+                let last = (self.levels.len() - 1) as Float;
+                let level = (width * size).log2().max(0.0).min(last);
+                let lower = level.floor();
+                let t = level - lower;
+                let lower = lower as usize;
+                if t == 0.0 {
+                    at(lower, true)
+                } else {
+                    (1.0 - t) * at(lower, true) + t * at(lower + 1, true)
+                }
+            },
+        }
+    }
+}
+
+/// Converts an 8 bit sRGB encoded channel to linear
+fn srgb_to_linear(c: u8) -> Float {
+    let c = (c as Float) / 255.0;
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeat_tiles_indices() {
+        let wrap = |i| Wrap::Repeat.apply(i, 4);
+        assert_eq!([wrap(0), wrap(3), wrap(4), wrap(9)], [0, 3, 0, 1]);
+        assert_eq!([wrap(-1), wrap(-4), wrap(-5)], [3, 0, 3]);
+    }
+
+    #[test]
+    fn clamp_extends_edges() {
+        let wrap = |i| Wrap::Clamp.apply(i, 4);
+        assert_eq!([wrap(0), wrap(3), wrap(4), wrap(100)], [0, 3, 3, 3]);
+        assert_eq!([wrap(-1), wrap(-100)], [0, 0]);
+    }
+
+    #[test]
+    fn mirror_flips_alternate_tiles() {
+        let wrap = |i| Wrap::Mirror.apply(i, 4);
+        assert_eq!([wrap(0), wrap(3), wrap(4), wrap(7), wrap(8)], [0, 3, 3, 0, 0]);
+        assert_eq!([wrap(-1), wrap(-4), wrap(-5), wrap(-9)], [0, 3, 3, 0]);
+    }
+
+    #[test]
+    fn single_pixel_wraps_to_itself() {
+        for &wrap in &[Wrap::Repeat, Wrap::Clamp, Wrap::Mirror] {
+            for i in -3..4 {
+                assert_eq!(wrap.apply(i, 1), 0);
+            }
+        }
+    }
+
+    #[test]
+    fn srgb_endpoints_are_preserved() {
+        assert_eq!(srgb_to_linear(0), 0.0);
+        assert!((srgb_to_linear(255) - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn srgb_is_continuous_at_the_knee() {
+        // 10 / 255 lies just below the 0.04045 knee and 11 / 255 just above,
+        // where the linear and power segments meet
+        let below = srgb_to_linear(10);
+        let above = srgb_to_linear(11);
+        assert!((below - 10.0 / 255.0 / 12.92).abs() < 1e-7);
+        assert!((above - ((11.0 / 255.0 + 0.055) / 1.055 as Float).powf(2.4)).abs() < 1e-7);
+        assert!(below < above);
+        let knee = 0.04045 as Float;
+        let power = ((knee + 0.055) / 1.055).powf(2.4);
+        assert!((knee / 12.92 - power).abs() < 1e-6);
+    }
+
+    #[test]
+    fn srgb_is_monotonic() {
+        for c in 0..255u8 {
+            assert!(srgb_to_linear(c) < srgb_to_linear(c + 1));
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use util::Float;
+
+/// The wavelength in nanometers at which the index of refraction of a
+/// dispersive material is given when no wavelength is being traced, the
+/// helium d-line
+const WAVELENGTH_D: Float = 587.6;
+
+#[derive(Debug, Clone, Copy)]
+pub enum Ior {
+    /// An index of refraction independent of wavelength
+    Constant { value: Float },
+    /// Cauchy's equation `a + b / λ²`, with λ in micrometers
+    Cauchy { a: Float, b: Float },
+    /// The Sellmeier equation `n² = 1 + Σ bλ² / (λ² - c)`, with λ in
+    /// micrometers
+    Sellmeier { b: [Float; 3], c: [Float; 3] },
+}
+
+impl Ior {
+    /// Creates a new constant index of refraction
+    pub fn constant(value: Float) -> Self {
+        Ior::Constant { value }
+    }
+
+    /// Creates a new index of refraction following Cauchy's equation
+    pub fn cauchy(a: Float, b: Float) -> Self {
+        Ior::Cauchy { a, b }
+    }
+
+    /// Creates a new index of refraction following the Sellmeier equation
+    pub fn sellmeier(b: [Float; 3], c: [Float; 3]) -> Self {
+        Ior::Sellmeier { b, c }
+    }
+
+    /// Creates the index of refraction of BK7 crown glass
+    pub fn bk7() -> Self {
+        Ior::sellmeier(
+            [1.03961212, 0.231792344, 1.01046945],
+            [0.00600069867, 0.0200179144, 103.560653],
+        )
+    }
+
+    /// Creates the index of refraction of SF11 dense flint glass, often used
+    /// for prisms
+    pub fn sf11() -> Self {
+        Ior::sellmeier(
+            [1.73759695, 0.313747346, 1.89878101],
+            [0.013188707, 0.0623068142, 155.23629],
+        )
+    }
+
+    /// Creates the index of refraction of diamond
+    pub fn diamond() -> Self {
+        Ior::sellmeier([0.3306, 4.3356, 0.0], [0.030625, 0.011236, 0.0])
+    }
+
+    /// Returns true if the index of refraction varies with wavelength
+    pub fn is_dispersive(&self) -> bool {
+        match self {
+            &Ior::Constant { .. } => false,
+            _ => true,
+        }
+    }
+
+    /// Gets the index of refraction at a wavelength in nanometers, or at the
+    /// d-line if no wavelength is given
+    pub fn at(&self, wavelength: Option<Float>) -> Float {
+        let l = wavelength.unwrap_or(WAVELENGTH_D) / 1000.0;
+        let l2 = l * l;
+        match self {
+            &Ior::Constant { value } => value,
+            &Ior::Cauchy { a, b } => a + b / l2,
+            &Ior::Sellmeier { b, c } => {
+                (1.0 + b[0] * l2 / (l2 - c[0])
+                     + b[1] * l2 / (l2 - c[1])
+                     + b[2] * l2 / (l2 - c[2])).sqrt()
+            },
+        }
+    }
+}
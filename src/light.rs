@@ -0,0 +1,222 @@
+use util::{Float, PI};
+use vec3::Vec3;
+use ray::Ray;
+use geo::Geo;
+use environment::Environment;
+use aabb::AABB;
+use ies::IesProfile;
+use spectrum::illuminant;
+use std::sync::Arc;
+
+#[derive(Debug, Clone)]
+pub enum Light {
+    /// A light backed by a primitive with an emissive material
+    Area {
+        geo: Geo,
+    },
+    /// A light emitting from a single point, equally in all directions
+    /// unless modulated by a photometric profile pointing down
+    Point {
+        position: Vec3,
+        intensity: Vec3,
+        profile: Option<Arc<IesProfile>>,
+    },
+    /// A point light emitting within a cone, falling off smoothly from
+    /// `cos_inner` to `cos_outer`, and optionally modulated by a photometric
+    /// profile pointing along the cone
+    Spot {
+        position: Vec3,
+        direction: Vec3,
+        intensity: Vec3,
+        cos_inner: Float,
+        cos_outer: Float,
+        profile: Option<Arc<IesProfile>>,
+    },
+    /// A light infinitely far away, arriving along a single direction
+    Directional {
+        direction: Vec3,
+        radiance: Vec3,
+    },
+    /// The environment surrounding the scene
+    Environment {
+        environment: Environment,
+    },
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum LightSample {
+    /// A direction towards an area or environment light, the radiance
+    /// arriving along it is found by tracing a ray
+    Traced {
+        dir: Vec3,
+    },
+    /// The radiance arriving from a delta light along a direction, if the
+    /// light is not occluded before the given distance
+    Delta {
+        dir: Vec3,
+        dist: Float,
+        radiance: Vec3,
+    },
+}
+
+impl Light {
+    /// Creates a new area light from a primitive
+    pub fn area(geo: Geo) -> Self {
+        Light::Area { geo }
+    }
+
+    /// Creates a new point light
+    pub fn point(position: Vec3, intensity: Vec3) -> Self {
+        Light::Point { position, intensity, profile: None }
+    }
+
+    /// Creates a new spot light.
+    ///
+    /// # Arguments
+    /// * `position`  - the position of the light
+    /// * `at`        - the point the light is aimed at
+    /// * `intensity` - the intensity along the center of the cone
+    /// * `inner`     - the angle in degrees at which falloff starts
+    /// * `outer`     - the angle in degrees at which the light ends
+    ///
+    pub fn spot(position: Vec3, at: Vec3, intensity: Vec3, inner: Float, outer: Float) -> Self {
+        Light::Spot {
+            position:  position,
+            direction: (at - position).unit(),
+            intensity: intensity,
+            cos_inner: inner.to_radians().cos(),
+            cos_outer: outer.to_radians().cos(),
+            profile:   None,
+        }
+    }
+
+    /// Modulates a point or spot light by a photometric profile. The light's
+    /// intensity then scales the profile's candela values.
+    pub fn with_profile(self, ies: IesProfile) -> Self {
+        let ies = Some(Arc::new(ies));
+        match self {
+            Light::Point { position, intensity, .. } => {
+                Light::Point { position, intensity, profile: ies }
+            },
+            Light::Spot { position, direction, intensity, cos_inner, cos_outer, .. } => {
+                Light::Spot { position, direction, intensity, cos_inner, cos_outer, profile: ies }
+            },
+            other => panic!("{:?} can not have a photometric profile!", other),
+        }
+    }
+
+    /// Creates a new environment light
+    pub fn environment(environment: Environment) -> Self {
+        Light::Environment { environment }
+    }
+
+    /// Creates a new directional light shining along the given direction
+    pub fn directional(direction: Vec3, radiance: Vec3) -> Self {
+        Light::Directional { direction: direction.unit(), radiance }
+    }
+
+    /// Samples the light arriving at a point
+    ///
+    /// # Arguments
+    /// * `origin`      - the point being lit
+    /// * `time`        - the time the light is sampled at
+    /// * `wavelengths` - the wavelengths traced by a spectral path, None when
+    ///                   tracing in RGB
+    ///
+    pub fn sample(&self, origin: Vec3, time: Float, wavelengths: Option<Vec3>) -> LightSample {
+        match self {
+            // Handle area lights
+            &Light::Area { ref geo } => LightSample::Traced {
+                dir: geo.random_direction(origin, time),
+            },
+            // Handle point lights, falling off with the distance squared
+            &Light::Point { position, intensity, ref profile } => {
+                let d = position - origin;
+                let scale = profile.as_ref().map_or(1.0, |ies| {
+                    ies.value(-d, Vec3::new(0.0, -1.0, 0.0))
+                });
+                LightSample::Delta {
+                    dir:      d.unit(),
+                    dist:     d.len(),
+                    radiance: scale * illuminant(intensity, wavelengths) / d.mag(),
+                }
+            },
+            // Handle spot lights, falling off with the distance squared and
+            // the angle off the cone's center
+            &Light::Spot { position, direction, intensity, cos_inner, cos_outer, ref profile } => {
+                let d = position - origin;
+                let cosine = Vec3::dot(-d.unit(), direction);
+                let falloff = if cosine < cos_outer {
+                    0.0
+                } else if cosine > cos_inner {
+                    1.0
+                } else {
+                    let delta = (cosine - cos_outer) / (cos_inner - cos_outer);
+                    delta * delta * delta * delta
+                };
+                let falloff = falloff * profile.as_ref().map_or(1.0, |ies| {
+                    ies.value(-d, direction)
+                });
+                LightSample::Delta {
+                    dir:      d.unit(),
+                    dist:     d.len(),
+                    radiance: falloff * illuminant(intensity, wavelengths) / d.mag(),
+                }
+            },
+            // Handle directional lights (trivial)
+            &Light::Directional { direction, radiance } => LightSample::Delta {
+                dir:      -direction,
+                dist:     Float::MAX,
+                radiance: illuminant(radiance, wavelengths),
+            },
+            // Handle environment lights
+            &Light::Environment { ref environment } => LightSample::Traced {
+                dir: environment.sample(),
+            },
+        }
+    }
+
+    /// Returns the bounds of the light, or None for lights infinitely far
+    /// away
+    pub fn bounds(&self) -> Option<AABB> {
+        let point = |p: Vec3| {
+            let pad = Vec3::ones() * 0.0001;
+            AABB::new(p - pad, p + pad)
+        };
+        match self {
+            &Light::Area { ref geo } => Some(geo.bounding_box()),
+            &Light::Point { position, .. } => Some(point(position)),
+            &Light::Spot { position, .. } => Some(point(position)),
+            _ => None,
+        }
+    }
+
+    /// Returns an estimate of the total power emitted by a bounded light
+    pub fn power(&self) -> Float {
+        match self {
+            &Light::Area { ref geo } => {
+                PI * geo.area() * geo.material().average_emission().luminance()
+            },
+            &Light::Point { intensity, ref profile, .. } => {
+                4.0 * PI * intensity.luminance()
+                    * profile.as_ref().map_or(1.0, |ies| ies.average())
+            },
+            &Light::Spot { intensity, cos_inner, cos_outer, ref profile, .. } => {
+                2.0 * PI * intensity.luminance()
+                    * (1.0 - 0.5 * (cos_inner + cos_outer))
+                    * profile.as_ref().map_or(1.0, |ies| ies.average())
+            },
+            _ => 0.0,
+        }
+    }
+
+    /// Returns the solid angle density with which `sample` produces the
+    /// direction of a ray from its origin, always zero for delta lights
+    pub fn pdf(&self, ray: Ray) -> Float {
+        match self {
+            &Light::Area { ref geo } => geo.pdf_value(ray),
+            &Light::Environment { ref environment } => environment.pdf(ray.dir),
+            _ => 0.0,
+        }
+    }
+}
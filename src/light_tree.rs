@@ -0,0 +1,185 @@
+use util::{rand, Float};
+use vec3::Vec3;
+use ray::Ray;
+use aabb::AABB;
+use light::Light;
+use std::cmp::Ordering::Equal;
+
+#[derive(Debug, Clone)]
+pub struct LightTree {
+    /// The hierarchy over every bounded light
+    root: Option<LightNode>,
+    /// The indices of lights infinitely far away, which can not be bounded
+    infinite: Vec<usize>,
+}
+
+#[derive(Debug, Clone)]
+enum LightNode {
+    /// A single light
+    Leaf {
+        light: usize,
+        bbox: AABB,
+        power: Float,
+    },
+    /// A pair of child nodes
+    Interior {
+        bbox: AABB,
+        power: Float,
+        left: Box<LightNode>,
+        right: Box<LightNode>,
+    },
+}
+
+impl LightTree {
+    /// Builds a light tree over the given lights. Lights are later chosen
+    /// proportional to their power over their distance squared from the
+    /// point being lit.
+    pub fn new(lights: &[Light]) -> Self {
+        let mut leaves   = Vec::new();
+        let mut infinite = Vec::new();
+        for (index, light) in lights.iter().enumerate() {
+            match light.bounds() {
+                Some(bbox) => leaves.push(LightNode::Leaf {
+                    light: index,
+                    bbox:  bbox,
+                    power: light.power(),
+                }),
+                None => infinite.push(index),
+            }
+        }
+        let root = if leaves.is_empty() { None } else { Some(LightNode::build(leaves)) };
+        LightTree { root, infinite }
+    }
+
+    /// The probability of choosing the tree rather than one of the infinite
+    /// lights, or any single infinite light
+    fn share(&self) -> Float {
+        let n = self.infinite.len() + if self.root.is_some() { 1 } else { 0 };
+        1.0 / (n as Float)
+    }
+
+    /// Chooses a light for a point being lit. Returns the index of the light
+    /// and the probability it was chosen with, or None if there are no lights
+    pub fn sample(&self, p: Vec3) -> Option<(usize, Float)> {
+        let n = self.infinite.len() + if self.root.is_some() { 1 } else { 0 };
+        if n == 0 {
+            return None;
+        }
+        let share = self.share();
+        let choice = ((rand() * n as Float) as usize).min(n - 1);
+        if choice < self.infinite.len() {
+            return Some((self.infinite[choice], share));
+        }
+        let mut node = self.root.as_ref().unwrap();
+        let mut pmf  = share;
+        loop {
+            match node {
+                &LightNode::Leaf { light, .. } => return Some((light, pmf)),
+                &LightNode::Interior { ref left, ref right, .. } => {
+                    let (pl, pr) = (left.importance(p), right.importance(p));
+                    if pl + pr <= 0.0 {
+                        return None;
+                    }
+                    if rand() * (pl + pr) < pl {
+                        pmf *= pl / (pl + pr);
+                        node = left;
+                    } else {
+                        pmf *= pr / (pl + pr);
+                        node = right;
+                    }
+                },
+            }
+        }
+    }
+
+    /// Returns the solid angle density with which choosing a light with
+    /// `sample` and then sampling it produces the direction of a ray from
+    /// its origin. Only the branches of the tree the ray hits are visited.
+    pub fn pdf(&self, lights: &[Light], ray: Ray) -> Float {
+        let share = self.share();
+        let mut pdf: Float = self.infinite
+            .iter()
+            .map(|&light| share * lights[light].pdf(ray))
+            .sum();
+        if let Some(ref root) = self.root {
+            pdf += share * root.pdf(lights, ray);
+        }
+        pdf
+    }
+}
+
+impl LightNode {
+    /// Recursively builds a node, splitting the lights at the median of
+    /// their centers along the widest axis
+    fn build(mut nodes: Vec<LightNode>) -> Self {
+        if nodes.len() == 1 {
+            return nodes.remove(0);
+        }
+        let mut centers = AABB::None;
+        for node in nodes.iter() {
+            let c = node.bbox().center();
+            centers = AABB::bound_boxes(centers, AABB::new(c, c));
+        }
+        let d = centers.diagonal();
+        let axis = if d.x > d.y && d.x > d.z { 0 } else if d.y > d.z { 1 } else { 2 };
+        nodes.sort_by(|a, b| {
+            a.bbox().center()[axis]
+                .partial_cmp(&b.bbox().center()[axis])
+                .unwrap_or(Equal)
+        });
+        let right = nodes.split_off(nodes.len() / 2);
+        let left  = LightNode::build(nodes);
+        let right = LightNode::build(right);
+        LightNode::Interior {
+            bbox:  AABB::bound_boxes(left.bbox(), right.bbox()),
+            power: left.power() + right.power(),
+            left:  Box::new(left),
+            right: Box::new(right),
+        }
+    }
+
+    /// Returns the bounds of the node
+    fn bbox(&self) -> AABB {
+        match self {
+            &LightNode::Leaf { bbox, .. } => bbox,
+            &LightNode::Interior { bbox, .. } => bbox,
+        }
+    }
+
+    /// Returns the total power of the node
+    fn power(&self) -> Float {
+        match self {
+            &LightNode::Leaf { power, .. } => power,
+            &LightNode::Interior { power, .. } => power,
+        }
+    }
+
+    /// Estimates the contribution of the node to a point being lit, its
+    /// power over the squared distance to its bounds, which is clamped by the
+    /// size of the node so points near or inside it do not favour it
+    /// unboundedly
+    fn importance(&self, p: Vec3) -> Float {
+        let bbox = self.bbox();
+        let d = bbox.diagonal().mag() / 4.0;
+        let dist2 = bbox.distance2(p).max(d * d);
+        if dist2 > 0.0 { self.power() / dist2 } else { self.power() }
+    }
+
+    /// Returns the density of sampling a ray's direction from the lights
+    /// below this node, relative to choosing this node
+    fn pdf(&self, lights: &[Light], ray: Ray) -> Float {
+        if !self.bbox().hit(ray, 0.0, Float::MAX) {
+            return 0.0;
+        }
+        match self {
+            &LightNode::Leaf { light, .. } => lights[light].pdf(ray),
+            &LightNode::Interior { ref left, ref right, .. } => {
+                let (pl, pr) = (left.importance(ray.origin), right.importance(ray.origin));
+                if pl + pr <= 0.0 {
+                    return 0.0;
+                }
+                (pl * left.pdf(lights, ray) + pr * right.pdf(lights, ray)) / (pl + pr)
+            },
+        }
+    }
+}
@@ -21,6 +21,22 @@ mod config;
 mod aabb;
 mod texture;
 mod noise;
+mod environment;
+mod light;
+mod distribution;
+mod hdr;
+mod exr;
+mod sky;
+mod light_tree;
+mod ies;
+mod microfacet;
+mod principled;
+mod medium;
+mod spectrum;
+mod ior;
+mod thin_film;
+mod image_map;
+mod bump;
 
 use std::fs::File;
 use std::io::Write;
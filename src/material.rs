@@ -1,17 +1,104 @@
 use vec3::Vec3;
 use ray::Ray;
 use geo::Intersection;
-use util::{rand, schlick};
+use util::{rand, schlick, Float, PI};
 use texture::Texture;
+use principled::Principled;
+use medium::Medium;
+use ior::Ior;
+use thin_film::ThinFilm;
+use bump::Bump;
+use spectrum::{illuminant, reflectance};
+use std::path::Path;
+use microfacet::{
+    Frame,
+    TrowbridgeReitz,
+    fresnel_conductor,
+    fresnel_dielectric,
+    reflect,
+    refract,
+    rough_dielectric,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Lobe {
+    /// A lobe scattering over the whole hemisphere
+    Diffuse,
+    /// A lobe scattering around a preferred direction
+    Glossy,
+    /// A delta lobe, such as a perfect mirror, which can only be sampled and
+    /// never evaluated with `eval` or `pdf`
+    Specular,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct BsdfSample {
+    /// The sampled direction of light leaving the intersection
+    pub dir: Vec3,
+    /// The BSDF times the cosine term divided by the density of the sample
+    pub weight: Vec3,
+    /// The solid angle density of the sample, or the discrete probability of
+    /// choosing a specular lobe
+    pub pdf: Float,
+    /// The lobe the direction was sampled from
+    pub lobe: Lobe,
+}
+
+impl BsdfSample {
+    /// Creates a new sample of a specular lobe
+    pub fn specular(dir: Vec3, weight: Vec3, pdf: Float) -> Self {
+        BsdfSample { dir, weight, pdf, lobe: Lobe::Specular }
+    }
+}
 
 #[derive(Debug, Clone)]
 pub enum Material {
     /// Material for lambertian (Diffuse) objects
     Lambertian { albedo: Texture },
     /// Material for metal (reflective) objects
-    Metal { albedo: Texture, fuzz: f64 },
-    /// Material for dielectric (refractive) objects
-    Dielectric { albedo: Texture, ref_idx: f64 },
+    Metal { albedo: Texture, fuzz: Float },
+    /// Material for dielectric (refractive) objects, with an optional
+    /// absorbing medium inside and thin film coating
+    Dielectric {
+        albedo: Texture,
+        ior: Ior,
+        medium: Option<Medium>,
+        film: Option<ThinFilm>,
+    },
+    /// Material for light emitting objects
+    DiffuseLight { emit: Texture },
+    /// Material for rough conductors, with a complex index of refraction
+    /// `eta + ik` per color channel, a GGX roughness and an optional thin
+    /// film coating
+    Conductor { eta: Vec3, k: Vec3, roughness: Float, film: Option<ThinFilm> },
+    /// Material for rough dielectric (refractive) objects with a GGX
+    /// roughness and an optional absorbing medium inside
+    RoughDielectric {
+        albedo: Texture,
+        ref_idx: Float,
+        roughness: Float,
+        medium: Option<Medium>,
+    },
+    /// Material for the Disney principled BSDF
+    Principled { bsdf: Box<Principled> },
+    /// Material for a dielectric coat layered over a base material, with an
+    /// absorption coefficient per color channel through its thickness
+    Coated {
+        base: Box<Material>,
+        ref_idx: Float,
+        roughness: Float,
+        absorption: Vec3,
+        thickness: Float,
+    },
+    /// Material blending two materials by the weight of a mask texture
+    Mix { a: Box<Material>, b: Box<Material>, mask: Texture },
+    /// Material for rough diffuse objects following the Oren-Nayar model,
+    /// with the standard deviation of the microfacet angle in degrees
+    OrenNayar { albedo: Texture, sigma: Float },
+    /// Material for thin diffuse sheets which reflect and transmit light
+    DiffuseTransmission { reflectance: Texture, transmittance: Texture },
+    /// Material whose shading normal is perturbed by a normal or bump map
+    Bumped { base: Box<Material>, bump: Bump },
 }
 
 impl Material {
@@ -19,55 +106,816 @@ impl Material {
     pub fn lambertian(albedo: Texture) -> Self {
         Material::Lambertian { albedo }
     }
+    /// Creates a new Oren-Nayar rough diffuse material, where a sigma of 0
+    /// degrees is lambertian
+    pub fn oren_nayar(albedo: Texture, sigma: Float) -> Self {
+        Material::OrenNayar { albedo, sigma }
+    }
+    /// Creates a new diffuse transmission material for thin sheets
+    pub fn diffuse_transmission(reflectance: Texture, transmittance: Texture) -> Self {
+        Material::DiffuseTransmission { reflectance, transmittance }
+    }
     /// Creates a new metal material
-    pub fn metal(albedo: Texture, fuzz: f64) -> Self {
+    pub fn metal(albedo: Texture, fuzz: Float) -> Self {
         Material::Metal { albedo, fuzz }
     }
     /// Creates a new dielectric material
-    pub fn dielectric(albedo: Texture, ref_idx: f64) -> Self {
-        Material::Dielectric { albedo, ref_idx }
+    pub fn dielectric(albedo: Texture, ref_idx: Float) -> Self {
+        Material::Dielectric { albedo, ior: Ior::constant(ref_idx), medium: None, film: None }
+    }
+    /// Creates a new dispersive dielectric material, whose index of
+    /// refraction varies with wavelength
+    pub fn dispersive(albedo: Texture, ior: Ior, medium: Option<Medium>) -> Self {
+        Material::Dielectric { albedo, ior, medium, film: None }
+    }
+    /// Creates a new clear dielectric material filled with an absorbing
+    /// medium, such as tinted glass
+    pub fn glass(ref_idx: Float, medium: Medium) -> Self {
+        Material::Dielectric {
+            albedo: Texture::solid(Vec3::ones()),
+            ior:    Ior::constant(ref_idx),
+            medium: Some(medium),
+            film:   None,
+        }
+    }
+    /// Creates a new diffuse light material
+    pub fn diffuse_light(emit: Texture) -> Self {
+        Material::DiffuseLight { emit }
+    }
+    /// Creates a new rough conductor material
+    pub fn conductor(eta: Vec3, k: Vec3, roughness: Float) -> Self {
+        Material::Conductor { eta, k, roughness, film: None }
+    }
+    /// Creates a new rough gold material
+    pub fn gold(roughness: Float) -> Self {
+        Material::conductor(
+            Vec3::new(0.143, 0.374, 1.442),
+            Vec3::new(3.983, 2.385, 1.603),
+            roughness,
+        )
+    }
+    /// Creates a new rough copper material
+    pub fn copper(roughness: Float) -> Self {
+        Material::conductor(
+            Vec3::new(0.200, 0.924, 1.102),
+            Vec3::new(3.912, 2.452, 2.142),
+            roughness,
+        )
+    }
+    /// Creates a new rough aluminium material
+    pub fn aluminium(roughness: Float) -> Self {
+        Material::conductor(
+            Vec3::new(1.657, 0.880, 0.521),
+            Vec3::new(9.224, 6.270, 4.837),
+            roughness,
+        )
+    }
+    /// Creates a new rough dielectric material
+    pub fn rough_dielectric(albedo: Texture, ref_idx: Float, roughness: Float) -> Self {
+        Material::RoughDielectric { albedo, ref_idx, roughness, medium: None }
+    }
+    /// Creates a new rough clear dielectric material filled with an
+    /// absorbing medium
+    pub fn rough_glass(ref_idx: Float, roughness: Float, medium: Medium) -> Self {
+        Material::RoughDielectric {
+            albedo: Texture::solid(Vec3::ones()),
+            ref_idx,
+            roughness,
+            medium: Some(medium),
+        }
+    }
+    /// Creates a new subsurface scattering material, a rough dielectric
+    /// boundary around a scattering medium that light takes a random walk
+    /// through. The geometry must be closed.
+    ///
+    /// # Arguments
+    /// * `ref_idx`        - the index of refraction of the boundary
+    /// * `roughness`      - the GGX roughness of the boundary
+    /// * `albedo`         - the fraction of light scattered rather than
+    ///                      absorbed at each interaction, per channel
+    /// * `mean_free_path` - the average distance between interactions, per
+    ///                      channel
+    ///
+    pub fn subsurface(
+        ref_idx: Float,
+        roughness: Float,
+        albedo: Vec3,
+        mean_free_path: Vec3,
+    ) -> Self {
+        Material::RoughDielectric {
+            albedo: Texture::solid(Vec3::ones()),
+            ref_idx,
+            roughness,
+            medium: Some(Medium::scattering(albedo, mean_free_path, 0.0)),
+        }
+    }
+    /// Coats a dielectric or conductor material with a thin film, whose
+    /// interference modulates the material's Fresnel reflectance
+    pub fn with_film(self, film: ThinFilm) -> Self {
+        match self {
+            Material::Dielectric { albedo, ior, medium, .. } => {
+                Material::Dielectric { albedo, ior, medium, film: Some(film) }
+            },
+            Material::Conductor { eta, k, roughness, .. } => {
+                Material::Conductor { eta, k, roughness, film: Some(film) }
+            },
+            other => panic!("{:?} can not have a thin film!", other),
+        }
+    }
+    /// Creates a new principled material
+    pub fn principled(bsdf: Principled) -> Self {
+        Material::Principled { bsdf: Box::new(bsdf) }
+    }
+    /// Creates a new coated material
+    ///
+    /// # Arguments
+    /// * `base`       - the material beneath the coat
+    /// * `ref_idx`    - the index of refraction of the coat
+    /// * `roughness`  - the GGX roughness of the coat's surface
+    /// * `absorption` - the absorption coefficient of the coat per channel
+    /// * `thickness`  - the thickness of the coat
+    ///
+    pub fn coated(
+        base: Material,
+        ref_idx: Float,
+        roughness: Float,
+        absorption: Vec3,
+        thickness: Float,
+    ) -> Self {
+        Material::Coated {
+            base: Box::new(base),
+            ref_idx,
+            roughness,
+            absorption,
+            thickness,
+        }
+    }
+    /// Creates a new material blending from `a` where the mask is 0 to `b`
+    /// where it is 1
+    pub fn mix(a: Material, b: Material, mask: Texture) -> Self {
+        Material::Mix { a: Box::new(a), b: Box::new(b), mask }
+    }
+    /// Perturbs the shading normal of the material by a tangent space normal
+    /// map image
+    pub fn with_normal_map<P: AsRef<Path>>(self, path: P) -> Self {
+        Material::Bumped { base: Box::new(self), bump: Bump::normal_map(path) }
+    }
+    /// Perturbs the shading normal of the material by a height texture,
+    /// whose luminance times `scale` displaces the surface
+    pub fn with_bump(self, texture: Texture, scale: Float) -> Self {
+        Material::Bumped { base: Box::new(self), bump: Bump::height(texture, scale) }
+    }
+
+    /// Returns true if the material emits light
+    pub fn is_emissive(&self) -> bool {
+        match self {
+            &Material::DiffuseLight { .. } => true,
+            &Material::Mix { ref a, ref b, .. } => a.is_emissive() || b.is_emissive(),
+            &Material::Bumped { ref base, .. } => base.is_emissive(),
+            _ => false,
+        }
+    }
+
+    /// Returns the medium filling the inside of the material, which a path
+    /// enters and leaves by being transmitted through the surface
+    pub fn medium(&self) -> Option<Medium> {
+        match self {
+            &Material::Dielectric { medium, .. } |
+            &Material::RoughDielectric { medium, .. } => medium,
+            &Material::Bumped { ref base, .. } => base.medium(),
+            _ => None,
+        }
+    }
+
+    /// Returns the index of refraction of the material at the intersection,
+    /// if light may be transmitted through its surface
+    pub fn ref_idx(&self, i: &Intersection) -> Option<Float> {
+        match self {
+            &Material::Dielectric { ior, .. } => Some(ior.at(i.ray.wavelength)),
+            &Material::RoughDielectric { ref_idx, .. } => Some(ref_idx),
+            &Material::Bumped { ref base, .. } => base.ref_idx(i),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the material scatters light differently by wavelength,
+    /// so paths hitting it must carry a single wavelength
+    pub fn is_dispersive(&self) -> bool {
+        match self {
+            &Material::Dielectric { ior, .. } => ior.is_dispersive(),
+            &Material::Coated { ref base, .. } => base.is_dispersive(),
+            &Material::Mix { ref a, ref b, .. } => a.is_dispersive() || b.is_dispersive(),
+            &Material::Bumped { ref base, .. } => base.is_dispersive(),
+            _ => false,
+        }
+    }
+
+    /// Returns true if every lobe of the material is a specular lobe, so the
+    /// material can not be light sampled
+    pub fn is_specular(&self) -> bool {
+        match self {
+            &Material::Lambertian { .. } |
+            &Material::OrenNayar { .. } |
+            &Material::DiffuseTransmission { .. } |
+            &Material::Principled { .. } => false,
+            &Material::Conductor { roughness, .. } |
+            &Material::RoughDielectric { roughness, .. } => {
+                TrowbridgeReitz::new(roughness).is_smooth()
+            },
+            &Material::Coated { ref base, roughness, .. } => {
+                TrowbridgeReitz::new(roughness).is_smooth() && base.is_specular()
+            },
+            &Material::Mix { ref a, ref b, .. } => a.is_specular() && b.is_specular(),
+            &Material::Bumped { ref base, .. } => base.is_specular(),
+            _ => true,
+        }
+    }
+
+    /// Evaluate the BSDF times the cosine term for light leaving the
+    /// intersection in the given direction
+    ///
+    /// # Arguments
+    /// * `i`           - the intersection
+    /// * `dir`         - the direction light leaves in
+    /// * `wavelengths` - the wavelengths traced by a spectral path, None when
+    ///                   tracing in RGB
+    ///
+    pub fn eval(&self, i: &Intersection, dir: Vec3, wavelengths: Option<Vec3>) -> Vec3 {
+        // Colors are upsampled where they are looked up, so spectral paths
+        // multiply spectra rather than colors
+        let color = |texture: &Texture| reflectance(texture.value(i), wavelengths);
+        match self {
+            &Material::Lambertian { ref albedo } => {
+                let cosine = Vec3::dot(i.normal, dir.unit()).max(0.0);
+                color(albedo) * cosine / PI
+            },
+            &Material::OrenNayar { ref albedo, sigma } => {
+                let frame = facing_frame(i);
+                let wo = frame.to_local(-i.ray.dir);
+                let wi = frame.to_local(dir.unit());
+                color(albedo) * oren_nayar(sigma, wo, wi)
+            },
+            &Material::DiffuseTransmission { ref reflectance, ref transmittance } => {
+                let cos_i = Vec3::dot(i.normal, dir.unit());
+                let cos_o = -Vec3::dot(i.normal, i.ray.dir);
+                if cos_i * cos_o > 0.0 {
+                    color(reflectance) * cos_i.abs() / PI
+                } else {
+                    color(transmittance) * cos_i.abs() / PI
+                }
+            },
+            &Material::Conductor { eta, k, roughness, film } => {
+                let mfd = TrowbridgeReitz::new(roughness);
+                let frame = facing_frame(i);
+                let wo = frame.to_local(-i.ray.dir);
+                let wi = frame.to_local(dir.unit());
+                match half_vector(wo, wi) {
+                    Some(wm) => conductor_fresnel(i, Vec3::dot(wo, wm), eta, k, film, wavelengths)
+                        * mfd.d(wm) * mfd.g(wo, wi) / (4.0 * wo.z),
+                    None => Vec3::zeros(),
+                }
+            },
+            &Material::RoughDielectric { ref albedo, ref_idx, roughness, .. } => {
+                let mfd = TrowbridgeReitz::new(roughness);
+                let frame = Frame::new(i.normal);
+                let wo = frame.to_local(-i.ray.dir);
+                let wi = frame.to_local(dir.unit());
+                color(albedo) * rough_dielectric(mfd, ref_idx, wo, wi).0
+            },
+            &Material::Principled { ref bsdf } => bsdf.eval(i, dir, wavelengths).0,
+            &Material::Coated { .. } => self.eval_coated(i, dir, wavelengths).0,
+            &Material::Mix { ref a, ref b, ref mask } => {
+                let w = mix_weight(mask, i);
+                a.eval(i, dir, wavelengths) * (1.0 - w) + b.eval(i, dir, wavelengths) * w
+            },
+            &Material::Bumped { ref base, ref bump } => {
+                base.eval(&bump.perturb(i), dir, wavelengths)
+            },
+            _ => Vec3::zeros(),
+        }
+    }
+
+    /// Returns the solid angle density with which `sample` scatters into the
+    /// given direction
+    pub fn pdf(&self, i: &Intersection, dir: Vec3) -> Float {
+        match self {
+            &Material::Lambertian { .. } => {
+                Vec3::dot(i.normal, dir.unit()).max(0.0) / PI
+            },
+            &Material::OrenNayar { .. } => {
+                Vec3::dot(facing_frame(i).n, dir.unit()).max(0.0) / PI
+            },
+            &Material::DiffuseTransmission { ref reflectance, ref transmittance } => {
+                let cos_i = Vec3::dot(i.normal, dir.unit());
+                let cos_o = -Vec3::dot(i.normal, i.ray.dir);
+                let chance = reflect_chance(reflectance.value(i), transmittance.value(i));
+                let chance = if cos_i * cos_o > 0.0 { chance } else { 1.0 - chance };
+                chance * cos_i.abs() / PI
+            },
+            &Material::Conductor { roughness, .. } => {
+                let mfd = TrowbridgeReitz::new(roughness);
+                let frame = facing_frame(i);
+                let wo = frame.to_local(-i.ray.dir);
+                let wi = frame.to_local(dir.unit());
+                match half_vector(wo, wi) {
+                    Some(wm) => mfd.pdf(wo, wm) / (4.0 * Vec3::dot(wo, wm)),
+                    None => 0.0,
+                }
+            },
+            &Material::RoughDielectric { ref_idx, roughness, .. } => {
+                let mfd = TrowbridgeReitz::new(roughness);
+                let frame = Frame::new(i.normal);
+                let wo = frame.to_local(-i.ray.dir);
+                let wi = frame.to_local(dir.unit());
+                rough_dielectric(mfd, ref_idx, wo, wi).1
+            },
+            &Material::Principled { ref bsdf } => bsdf.eval(i, dir, None).1,
+            &Material::Coated { .. } => self.eval_coated(i, dir, None).1,
+            &Material::Mix { ref a, ref b, ref mask } => {
+                let w = mix_weight(mask, i);
+                a.pdf(i, dir) * (1.0 - w) + b.pdf(i, dir) * w
+            },
+            &Material::Bumped { ref base, ref bump } => base.pdf(&bump.perturb(i), dir),
+            _ => 0.0,
+        }
+    }
+
+    /// Returns the approximate average radiance emitted by the material
+    pub fn average_emission(&self) -> Vec3 {
+        match self {
+            &Material::DiffuseLight { ref emit } => emit.average(),
+            &Material::Mix { ref a, ref b, ref mask } => {
+                let w = mask.average().luminance().max(0.0).min(1.0);
+                a.average_emission() * (1.0 - w) + b.average_emission() * w
+            },
+            &Material::Bumped { ref base, .. } => base.average_emission(),
+            _ => Vec3::zeros(),
+        }
     }
 
-    /// Determine the shade at a given intersection
-    pub fn shade(&self, i: &Intersection) -> (Vec3, Option<Ray>) {
+    /// Determine the light emitted at a given intersection
+    ///
+    /// # Arguments
+    /// * `i`           - the intersection
+    /// * `wavelengths` - the wavelengths traced by a spectral path, None when
+    ///                   tracing in RGB
+    ///
+    pub fn emitted(&self, i: &Intersection, wavelengths: Option<Vec3>) -> Vec3 {
         match self {
-            // Handle material for lambertian
+            &Material::DiffuseLight { ref emit } => illuminant(emit.value(i), wavelengths),
+            &Material::Mix { ref a, ref b, ref mask } => {
+                let w = mix_weight(mask, i);
+                a.emitted(i, wavelengths) * (1.0 - w) + b.emitted(i, wavelengths) * w
+            },
+            &Material::Bumped { ref base, ref bump } => {
+                base.emitted(&bump.perturb(i), wavelengths)
+            },
+            _ => Vec3::zeros(),
+        }
+    }
+
+    /// Samples a direction for light leaving the intersection, returning
+    /// None if the material absorbs the incoming ray
+    ///
+    /// # Arguments
+    /// * `i`           - the intersection
+    /// * `wavelengths` - the wavelengths traced by a spectral path, None when
+    ///                   tracing in RGB
+    ///
+    pub fn sample(&self, i: &Intersection, wavelengths: Option<Vec3>) -> Option<BsdfSample> {
+        let color = |texture: &Texture| reflectance(texture.value(i), wavelengths);
+        match self {
+            // Handle material for lambertian, a point on the unit sphere
+            // around the normal gives a cosine weighted direction
             &Material::Lambertian { ref albedo } => {
-                let target = i.point + i.normal + Vec3::rand();
-                (albedo.value(i), Some(Ray::from_intersection(i, target - i.point)))
+                let dir = (i.normal + Vec3::rand_unit()).unit();
+                Some(BsdfSample {
+                    dir,
+                    weight: color(albedo),
+                    pdf:    Vec3::dot(i.normal, dir).max(0.0) / PI,
+                    lobe:   Lobe::Diffuse,
+                })
             }
-            // Handle material for metal
-            &Material::Metal { ref albedo, fuzz } => {
-                let scattered = Ray::from_intersection(i, i.reflected() + fuzz * Vec3::rand());
-                (albedo.value(i), if Vec3::dot(scattered.dir, i.normal) > 0.0 {
-                    Some(scattered)
+            // Handle material for oren-nayar, sampled like a lambertian
+            &Material::OrenNayar { ref albedo, sigma } => {
+                let frame = facing_frame(i);
+                let dir = (frame.n + Vec3::rand_unit()).unit();
+                let wo = frame.to_local(-i.ray.dir);
+                let wi = frame.to_local(dir);
+                let pdf = wi.z.max(0.0) / PI;
+                if pdf <= 0.0 {
+                    return None;
+                }
+                Some(BsdfSample {
+                    dir,
+                    weight: color(albedo) * oren_nayar(sigma, wo, wi) / pdf,
+                    pdf,
+                    lobe:   Lobe::Diffuse,
+                })
+            },
+            // Handle material for diffuse transmission, choosing to reflect or
+            // transmit and then sampling a cosine weighted direction
+            &Material::DiffuseTransmission { ref reflectance, ref transmittance } => {
+                let chance = reflect_chance(reflectance.value(i), transmittance.value(i));
+                let facing = facing_frame(i).n;
+                let (normal, weight, chance) = if rand() < chance {
+                    (facing, color(reflectance), chance)
                 } else {
-                    (None)
+                    (-facing, color(transmittance), 1.0 - chance)
+                };
+                let dir = (normal + Vec3::rand_unit()).unit();
+                Some(BsdfSample {
+                    dir,
+                    weight: weight / chance,
+                    pdf:    chance * Vec3::dot(normal, dir).max(0.0) / PI,
+                    lobe:   Lobe::Diffuse,
                 })
             },
+            // Handle material for metal, which is only ever sampled
+            &Material::Metal { ref albedo, fuzz } => {
+                let dir = i.reflected() + fuzz * Vec3::rand();
+                if Vec3::dot(dir, i.normal) > 0.0 {
+                    Some(BsdfSample::specular(dir, color(albedo), 1.0))
+                } else {
+                    None
+                }
+            },
             // Handle material for dielectrics
-            &Material::Dielectric { ref albedo, ref_idx } => {
+            &Material::Dielectric { ref albedo, ior, film, .. } => {
+                let ref_idx = ior.at(i.ray.wavelength);
                 let (normal, k, cosine) = if Vec3::dot(i.ray.dir, i.normal) > 0.0 {
                     (-i.normal, ref_idx, ref_idx * Vec3::dot(i.ray.dir, i.normal))
                 } else {
                     (i.normal, 1.0 / ref_idx, -Vec3::dot(i.ray.dir, i.normal))
                 };
-                let (refracted, p) = match Vec3::refract(i.ray.dir, normal, k) {
+                let refracted = Vec3::refract(i.ray.dir, normal, k);
+                // A film reflects each color differently, so the lobe is
+                // chosen by the average reflectance and weighted per color
+                if let (Some(film), Some(refracted)) = (film, refracted) {
+                    // The film coats the outside, so from inside the glass is
+                    // the incident medium and the air the base
+                    let (n, base) = if Vec3::dot(i.ray.dir, i.normal) > 0.0 {
+                        (ref_idx, 1.0)
+                    } else {
+                        (1.0, ref_idx)
+                    };
+                    let r = film_reflectance(
+                        i,
+                        film,
+                        Vec3::dot(i.ray.dir, i.normal),
+                        n,
+                        Vec3::ones() * base,
+                        Vec3::zeros(),
+                        wavelengths,
+                    );
+                    let p = (r.x + r.y + r.z) / 3.0;
+                    return Some(if rand() < p {
+                        BsdfSample::specular(i.reflected(), color(albedo) * r / p, p)
+                    } else {
+                        let t = Vec3::ones() - r;
+                        BsdfSample::specular(refracted, color(albedo) * t / (1.0 - p), 1.0 - p)
+                    });
+                }
+                let (refracted, p) = match refracted {
                     Some(refracted) => (refracted, schlick(cosine, ref_idx)),
                     None => (Vec3::ones(), 1.0),
                 };
-                (
-                    albedo.value(i),
-                    Some(Ray::from_intersection(i, if rand() < p {
-                            i.reflected()
-                        } else {
-                            refracted
-                        },
-                    )),
-                )
-            }
+                Some(if rand() < p {
+                    BsdfSample::specular(i.reflected(), color(albedo), p)
+                } else {
+                    BsdfSample::specular(refracted, color(albedo), 1.0 - p)
+                })
+            },
+            // Handle material for lights, which absorb everything
+            &Material::DiffuseLight { .. } => None,
+            // Handle material for conductors, reflecting off a sampled
+            // visible microfacet
+            &Material::Conductor { eta, k, roughness, film } => {
+                let mfd = TrowbridgeReitz::new(roughness);
+                let frame = facing_frame(i);
+                let wo = frame.to_local(-i.ray.dir);
+                if mfd.is_smooth() {
+                    let wi = Vec3::new(-wo.x, -wo.y, wo.z);
+                    return Some(BsdfSample::specular(
+                        frame.from_local(wi),
+                        conductor_fresnel(i, wo.z, eta, k, film, wavelengths),
+                        1.0,
+                    ));
+                }
+                let wm = mfd.sample_wm(wo, rand(), rand());
+                let wi = reflect(wo, wm);
+                if wi.z <= 0.0 {
+                    return None;
+                }
+                Some(BsdfSample {
+                    dir:    frame.from_local(wi),
+                    weight: conductor_fresnel(i, Vec3::dot(wo, wm), eta, k, film, wavelengths)
+                        * mfd.g(wo, wi) / mfd.g1(wo),
+                    pdf:    mfd.pdf(wo, wm) / (4.0 * Vec3::dot(wo, wm)),
+                    lobe:   Lobe::Glossy,
+                })
+            },
+            // Handle material for rough dielectrics, reflecting off or
+            // refracting through a sampled visible microfacet
+            &Material::RoughDielectric { ref albedo, ref_idx, roughness, .. } => {
+                let mfd = TrowbridgeReitz::new(roughness);
+                let frame = Frame::new(i.normal);
+                let wo = frame.to_local(-i.ray.dir);
+                let wm = if mfd.is_smooth() {
+                    Vec3::new(0.0, 0.0, 1.0)
+                } else {
+                    mfd.sample_wm(wo, rand(), rand())
+                };
+                let r = fresnel_dielectric(Vec3::dot(wo, wm), ref_idx);
+                let reflected = rand() < r;
+                let wi = if reflected {
+                    reflect(wo, wm)
+                } else {
+                    refract(wo, wm, ref_idx)?.0
+                };
+                // A microfacet can reflect through or refract back across
+                // the macro surface, such paths are not part of either lobe
+                if reflected != (wi.z * wo.z > 0.0) {
+                    return None;
+                }
+                if mfd.is_smooth() {
+                    let chance = if reflected { r } else { 1.0 - r };
+                    return Some(BsdfSample::specular(frame.from_local(wi), color(albedo), chance));
+                }
+                let (f, pdf) = rough_dielectric(mfd, ref_idx, wo, wi);
+                if pdf <= 0.0 {
+                    return None;
+                }
+                Some(BsdfSample {
+                    dir:    frame.from_local(wi),
+                    weight: color(albedo) * f / pdf,
+                    pdf,
+                    lobe:   Lobe::Glossy,
+                })
+            },
+            // Handle material for the principled BSDF, sampling one of its
+            // lobes and weighting by the density of all of them
+            &Material::Principled { ref bsdf } => {
+                let dir = bsdf.sample(i)?;
+                let (f, pdf) = bsdf.eval(i, dir, wavelengths);
+                if pdf <= 0.0 {
+                    return None;
+                }
+                Some(BsdfSample { dir, weight: f / pdf, pdf, lobe: Lobe::Glossy })
+            },
+            // Handle material for coats, either reflecting off the coat or
+            // refracting through it to scatter off the base and back out
+            &Material::Coated { ref base, ref_idx, roughness, absorption, thickness } => {
+                let mfd = TrowbridgeReitz::new(roughness);
+                let frame = facing_frame(i);
+                let wo = frame.to_local(-i.ray.dir);
+                let r = fresnel_dielectric(wo.z, ref_idx);
+                if rand() < r {
+                    if mfd.is_smooth() {
+                        let wi = Vec3::new(-wo.x, -wo.y, wo.z);
+                        return Some(BsdfSample::specular(frame.from_local(wi), Vec3::ones(), r));
+                    }
+                    let wi = reflect(wo, mfd.sample_wm(wo, rand(), rand()));
+                    if wi.z <= 0.0 {
+                        return None;
+                    }
+                    let dir = frame.from_local(wi);
+                    let (f, pdf) = self.eval_coated(i, dir, wavelengths);
+                    return Some(BsdfSample { dir, weight: f / pdf, pdf, lobe: Lobe::Glossy });
+                }
+                // Refract into the coat and scatter off the base
+                let wt = refract(wo, Vec3::new(0.0, 0.0, 1.0), ref_idx)?.0;
+                let below = beneath(i, frame.from_local(wt), base);
+                let sample = base.sample(&below, wavelengths)?;
+                let wb = frame.to_local(sample.dir);
+                let absorption = reflectance(absorption, wavelengths);
+                let absorbed = -absorption * thickness / wt.z.abs();
+                // Light scattered down through the base leaves the coat there
+                if wb.z <= 0.0 {
+                    return Some(BsdfSample {
+                        weight: sample.weight * absorbed.exp(),
+                        pdf:    sample.pdf * (1.0 - r),
+                        ..sample
+                    });
+                }
+                // Otherwise it is refracted back out through the coat
+                let wi = refract(-wb, Vec3::new(0.0, 0.0, 1.0), ref_idx)?.0;
+                let dir = frame.from_local(wi);
+                if sample.lobe == Lobe::Specular {
+                    let transmitted = 1.0 - fresnel_dielectric(wi.z, ref_idx);
+                    let absorbed = absorbed - absorption * thickness / wb.z;
+                    return Some(BsdfSample {
+                        dir,
+                        weight: sample.weight * absorbed.exp() * transmitted,
+                        pdf:    sample.pdf * (1.0 - r),
+                        lobe:   Lobe::Specular,
+                    });
+                }
+                let (f, pdf) = self.eval_coated(i, dir, wavelengths);
+                if pdf <= 0.0 {
+                    return None;
+                }
+                Some(BsdfSample { dir, weight: f / pdf, pdf, lobe: sample.lobe })
+            },
+            // Handle material for mixes, sampling one of the two materials
+            // and weighting by the density of both
+            &Material::Mix { ref a, ref b, ref mask } => {
+                let w = mix_weight(mask, i);
+                let (sample, chance) = if rand() < w {
+                    (b.sample(i, wavelengths)?, w)
+                } else {
+                    (a.sample(i, wavelengths)?, 1.0 - w)
+                };
+                if sample.lobe == Lobe::Specular {
+                    return Some(BsdfSample { pdf: sample.pdf * chance, ..sample });
+                }
+                let pdf = self.pdf(i, sample.dir);
+                if pdf <= 0.0 {
+                    return None;
+                }
+                let weight = self.eval(i, sample.dir, wavelengths) / pdf;
+                Some(BsdfSample { weight, pdf, ..sample })
+            },
+            // Handle material for bump maps, sampling the base material
+            // around the perturbed normal
+            &Material::Bumped { ref base, ref bump } => {
+                base.sample(&bump.perturb(i), wavelengths)
+            },
         }
     }
+
+    /// Evaluates the non specular lobes of a coated material, returning the
+    /// BSDF times the cosine term and the density of sampling `dir`
+    fn eval_coated(
+        &self,
+        i: &Intersection,
+        dir: Vec3,
+        wavelengths: Option<Vec3>,
+    ) -> (Vec3, Float) {
+        let (base, ref_idx, roughness, absorption, thickness) = match self {
+            &Material::Coated { ref base, ref_idx, roughness, absorption, thickness } => {
+                (base, ref_idx, roughness, absorption, thickness)
+            },
+            _ => return (Vec3::zeros(), 0.0),
+        };
+        let mfd = TrowbridgeReitz::new(roughness);
+        let frame = facing_frame(i);
+        let wo = frame.to_local(-i.ray.dir);
+        let wi = frame.to_local(dir.unit());
+        let r = fresnel_dielectric(wo.z, ref_idx);
+        let (mut f, mut pdf) = (Vec3::zeros(), 0.0);
+        // Reflection off the coat
+        if let (false, Some(wm)) = (mfd.is_smooth(), half_vector(wo, wi)) {
+            f = f + Vec3::ones() * fresnel_dielectric(Vec3::dot(wo, wm), ref_idx)
+                * mfd.d(wm) * mfd.g(wo, wi) / (4.0 * wo.z);
+            pdf += r * mfd.pdf(wo, wm) / (4.0 * Vec3::dot(wo, wm));
+        }
+        // Scattering off the base, seen through the coat
+        let wt = match refract(wo, Vec3::new(0.0, 0.0, 1.0), ref_idx) {
+            Some((wt, _)) => wt,
+            None => return (f, pdf),
+        };
+        let below = beneath(i, frame.from_local(wt), base);
+        let absorption = reflectance(absorption, wavelengths);
+        let absorbed = -absorption * thickness / wt.z.abs();
+        if wi.z <= 0.0 {
+            f = f + base.eval(&below, dir, wavelengths) * absorbed.exp() * (1.0 - r);
+            pdf += base.pdf(&below, dir) * (1.0 - r);
+            return (f, pdf);
+        }
+        let wb = match refract(wi, Vec3::new(0.0, 0.0, 1.0), ref_idx) {
+            Some((wb, _)) => -wb,
+            None => return (f, pdf),
+        };
+        let absorbed = absorbed - absorption * thickness / wb.z;
+        let transmitted = (1.0 - r) * (1.0 - fresnel_dielectric(wi.z, ref_idx));
+        // The solid angle density changes across the refraction
+        let jacobian = wi.z / (ref_idx * ref_idx * wb.z);
+        let dir = frame.from_local(wb);
+        f = f + base.eval(&below, dir, wavelengths) * absorbed.exp() * transmitted * jacobian;
+        pdf += base.pdf(&below, dir) * (1.0 - r) * jacobian;
+        (f, pdf)
+    }
 }
 
+/// Returns the weight of the second material of a mix at an intersection
+fn mix_weight(mask: &Texture, i: &Intersection) -> Float {
+    mask.value(i).luminance().max(0.0).min(1.0)
+}
 
+/// Returns the intersection seen by the base of a coated material, hit by a
+/// ray refracted through the coat
+fn beneath(i: &Intersection, dir: Vec3, base: &Material) -> Intersection {
+    Intersection {
+        ray:      Ray { origin: i.point, dir: dir.unit(), ..i.ray },
+        material: base.clone(),
+        ..i.clone()
+    }
+}
+
+/// Returns a shading frame around the intersection normal, flipped to face
+/// the incoming ray for materials which are two sided
+fn facing_frame(i: &Intersection) -> Frame {
+    if Vec3::dot(i.ray.dir, i.normal) > 0.0 {
+        Frame::new(-i.normal)
+    } else {
+        Frame::new(i.normal)
+    }
+}
+
+/// Evaluates the Oren-Nayar BRDF times the cosine term for unit albedo
+/// between two local directions
+///
+/// # Arguments
+/// * `sigma` - the standard deviation of the microfacet angle in degrees
+/// * `wo`    - the outgoing direction, towards the viewer
+/// * `wi`    - the incident direction, towards the light
+///
+fn oren_nayar(sigma: Float, wo: Vec3, wi: Vec3) -> Float {
+    if wo.z <= 0.0 || wi.z <= 0.0 {
+        return 0.0;
+    }
+    let sigma = sigma.to_radians();
+    let sigma2 = sigma * sigma;
+    let a = 1.0 - sigma2 / (2.0 * (sigma2 + 0.33));
+    let b = 0.45 * sigma2 / (sigma2 + 0.09);
+    let sin_i = (1.0 - wi.z * wi.z).max(0.0).sqrt();
+    let sin_o = (1.0 - wo.z * wo.z).max(0.0).sqrt();
+    // The cosine of the azimuthal angle between the directions
+    let cos_phi = if sin_i > 1e-4 && sin_o > 1e-4 {
+        ((wi.x * wo.x + wi.y * wo.y) / (sin_i * sin_o)).max(0.0)
+    } else {
+        0.0
+    };
+    // The sine of the larger angle and the tangent of the smaller
+    let (sin_alpha, tan_beta) = if wi.z > wo.z {
+        (sin_o, sin_i / wi.z)
+    } else {
+        (sin_i, sin_o / wo.z)
+    };
+    (a + b * cos_phi * sin_alpha * tan_beta) * wi.z / PI
+}
+
+/// Returns the chance a diffuse transmission material samples reflection,
+/// by the relative brightness of its reflectance and transmittance
+fn reflect_chance(reflectance: Vec3, transmittance: Vec3) -> Float {
+    let (r, t) = (reflectance.luminance(), transmittance.luminance());
+    if r + t > 0.0 { r / (r + t) } else { 0.5 }
+}
+
+/// Returns the Fresnel reflectance of a conductor, modulated by its thin
+/// film if it has one. Spectral paths upsample the index of refraction and
+/// take the reflectance at each of their wavelengths.
+fn conductor_fresnel(
+    i: &Intersection,
+    cos_i: Float,
+    eta: Vec3,
+    k: Vec3,
+    film: Option<ThinFilm>,
+    wavelengths: Option<Vec3>,
+) -> Vec3 {
+    let (eta, k) = (reflectance(eta, wavelengths), reflectance(k, wavelengths));
+    match film {
+        Some(film) => film_reflectance(i, film, cos_i, 1.0, eta, k, wavelengths),
+        None => fresnel_conductor(cos_i, eta, k),
+    }
+}
+
+/// Returns the reflectance of a thin film over a base, at each wavelength of
+/// a spectral path or otherwise in RGB
+///
+/// # Arguments
+/// * `i`           - the intersection
+/// * `film`        - the film
+/// * `cos_i`       - the cosine of the incident angle
+/// * `n`           - the index of refraction of the incident medium
+/// * `eta`         - the real part of the base's index of refraction
+/// * `k`           - the imaginary part of the base's index of refraction
+/// * `wavelengths` - the wavelengths traced by a spectral path, None when
+///                   tracing in RGB
+///
+fn film_reflectance(
+    i: &Intersection,
+    film: ThinFilm,
+    cos_i: Float,
+    n: Float,
+    eta: Vec3,
+    k: Vec3,
+    wavelengths: Option<Vec3>,
+) -> Vec3 {
+    match wavelengths {
+        Some(wavelengths) => film.reflectance_spectrum(cos_i, n, eta, k, wavelengths),
+        None => film.reflectance_rgb(cos_i, n, eta, k, i.ray.wavelength),
+    }
+}
+
+/// Returns the microfacet normal reflecting between two local directions
+/// above the surface, or None if either is below it
+fn half_vector(wo: Vec3, wi: Vec3) -> Option<Vec3> {
+    let wm = wo + wi;
+    if wo.z <= 0.0 || wi.z <= 0.0 || wm.mag() == 0.0 {
+        None
+    } else {
+        Some(wm.unit())
+    }
+}
@@ -0,0 +1,120 @@
+use util::{rand, Float, PI};
+use vec3::Vec3;
+use spectrum::reflectance;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Medium {
+    /// The absorption coefficient per color channel, per unit distance
+    pub absorption: Vec3,
+    /// The scattering coefficient per color channel, per unit distance
+    pub scattering: Vec3,
+    /// The Henyey-Greenstein asymmetry of scattering, from -1 for back
+    /// scattering through 0 for isotropic to 1 for forward scattering
+    pub g: Float,
+}
+
+impl Medium {
+    /// Creates a new absorbing medium
+    pub fn new(absorption: Vec3) -> Self {
+        Medium { absorption, scattering: Vec3::zeros(), g: 0.0 }
+    }
+
+    /// Creates a new absorbing medium which lets through the given color
+    /// after light has travelled a given distance through it
+    ///
+    /// # Arguments
+    /// * `color` - the color transmitted at the given distance
+    /// * `dist`  - the distance at which `color` is reached
+    ///
+    pub fn from_color(color: Vec3, dist: Float) -> Self {
+        let channel = |c: Float| -c.max(1e-6).min(1.0).ln() / dist;
+        Medium::new(Vec3::new(channel(color.x), channel(color.y), channel(color.z)))
+    }
+
+    /// Creates a new scattering medium
+    ///
+    /// # Arguments
+    /// * `albedo`         - the fraction of light scattered rather than
+    ///                      absorbed at each interaction, per channel
+    /// * `mean_free_path` - the average distance between interactions, per
+    ///                      channel
+    /// * `g`              - the asymmetry of scattering
+    ///
+    pub fn scattering(albedo: Vec3, mean_free_path: Vec3, g: Float) -> Self {
+        let extinction = Vec3::new(
+            1.0 / mean_free_path.x,
+            1.0 / mean_free_path.y,
+            1.0 / mean_free_path.z,
+        );
+        Medium {
+            absorption: (Vec3::ones() - albedo) * extinction,
+            scattering: albedo * extinction,
+            g,
+        }
+    }
+
+    /// Returns true if light scatters inside the medium rather than only
+    /// being absorbed
+    pub fn is_scattering(&self) -> bool {
+        self.scattering.x > 0.0 || self.scattering.y > 0.0 || self.scattering.z > 0.0
+    }
+
+    /// Returns the fraction of light per channel, or per wavelength of a
+    /// spectral path, left after travelling a distance through the medium
+    ///
+    /// # Arguments
+    /// * `dist`        - the distance travelled
+    /// * `wavelengths` - the wavelengths traced by a spectral path, None when
+    ///                   tracing in RGB
+    ///
+    pub fn transmittance(&self, dist: Float, wavelengths: Option<Vec3>) -> Vec3 {
+        (-reflectance(self.absorption, wavelengths) * dist).exp()
+    }
+
+    /// Samples the distance to the next scattering event along a ray through
+    /// the medium, returning it if it comes before the given distance to the
+    /// next surface, along with the weight of the sample. Distances are
+    /// sampled by the extinction of a randomly chosen channel or wavelength.
+    ///
+    /// # Arguments
+    /// * `dist`        - the distance to the next surface
+    /// * `wavelengths` - the wavelengths traced by a spectral path, None when
+    ///                   tracing in RGB
+    ///
+    pub fn sample(&self, dist: Float, wavelengths: Option<Vec3>) -> (Option<Float>, Vec3) {
+        let scattering = reflectance(self.scattering, wavelengths);
+        let extinction = reflectance(self.absorption, wavelengths) + scattering;
+        let sigma = extinction[((rand() * 3.0) as u32).min(2)];
+        let t = if sigma > 0.0 { -(1.0 - rand()).ln() / sigma } else { Float::INFINITY };
+        let average = |v: Vec3| (v.x + v.y + v.z) / 3.0;
+        if t < dist {
+            let transmittance = (-extinction * t).exp();
+            let pdf = average(extinction * transmittance);
+            (Some(t), scattering * transmittance / pdf)
+        } else {
+            let transmittance = (-extinction * dist).exp();
+            (None, transmittance / average(transmittance))
+        }
+    }
+
+    /// Samples the direction light continues in after scattering, by the
+    /// Henyey-Greenstein phase function
+    ///
+    /// # Arguments
+    /// * `dir` - the direction light was travelling in
+    ///
+    pub fn sample_direction(&self, dir: Vec3) -> Vec3 {
+        let (u1, u2) = (rand(), rand());
+        let g = self.g;
+        let cos_theta = if g.abs() < 1e-3 {
+            1.0 - 2.0 * u1
+        } else {
+            let s = (1.0 - g * g) / (1.0 - g + 2.0 * g * u1);
+            (1.0 + g * g - s * s) / (2.0 * g)
+        };
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * PI * u2;
+        let (u, v) = Vec3::basis(dir);
+        (sin_theta * phi.cos() * u + sin_theta * phi.sin() * v + cos_theta * dir).unit()
+    }
+}
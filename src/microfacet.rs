@@ -0,0 +1,334 @@
+use util::{Float, PI};
+use vec3::Vec3;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Frame {
+    /// The first tangent
+    pub s: Vec3,
+    /// The second tangent
+    pub t: Vec3,
+    /// The normal
+    pub n: Vec3,
+}
+
+impl Frame {
+    /// Creates a shading frame around a unit normal
+    pub fn new(n: Vec3) -> Self {
+        let (s, t) = Vec3::basis(n);
+        Frame { s, t, n }
+    }
+
+    /// Creates a shading frame around a unit normal with its first tangent
+    /// along the given surface tangent, made orthogonal to the normal. Falls
+    /// back to an arbitrary tangent if it is parallel to the normal.
+    pub fn from_tangent(n: Vec3, tangent: Vec3) -> Self {
+        let s = tangent - Vec3::dot(tangent, n) * n;
+        if s.mag() <= 0.0 {
+            return Frame::new(n);
+        }
+        let s = s.unit();
+        Frame { s, t: Vec3::cross(n, s), n }
+    }
+
+    /// Converts a world space vector into the frame, with z along the normal
+    pub fn to_local(&self, v: Vec3) -> Vec3 {
+        Vec3::new(Vec3::dot(v, self.s), Vec3::dot(v, self.t), Vec3::dot(v, self.n))
+    }
+
+    /// Converts a vector in the frame back into world space
+    pub fn from_local(&self, v: Vec3) -> Vec3 {
+        v.x * self.s + v.y * self.t + v.z * self.n
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TrowbridgeReitz {
+    /// The roughness along the first tangent
+    pub alpha_x: Float,
+    /// The roughness along the second tangent
+    pub alpha_y: Float,
+}
+
+impl TrowbridgeReitz {
+    /// Creates a new distribution from a perceptual roughness in [0, 1],
+    /// which is squared to give the distribution's alpha
+    pub fn new(roughness: Float) -> Self {
+        TrowbridgeReitz::anisotropic(roughness, roughness)
+    }
+
+    /// Creates a new distribution with a separate roughness along each
+    /// tangent
+    pub fn anisotropic(roughness_x: Float, roughness_y: Float) -> Self {
+        TrowbridgeReitz {
+            alpha_x: (roughness_x * roughness_x).max(1e-4),
+            alpha_y: (roughness_y * roughness_y).max(1e-4),
+        }
+    }
+
+    /// Returns true if the surface is smooth enough to be treated as a
+    /// perfect mirror
+    pub fn is_smooth(&self) -> bool {
+        self.alpha_x.max(self.alpha_y) < 1e-3
+    }
+
+    /// The density of microfacets with the given local normal
+    pub fn d(&self, wm: Vec3) -> Float {
+        let x = wm.x / self.alpha_x;
+        let y = wm.y / self.alpha_y;
+        let e = x * x + y * y + wm.z * wm.z;
+        1.0 / (PI * self.alpha_x * self.alpha_y * e * e)
+    }
+
+    /// The Smith auxiliary function of a local direction
+    pub fn lambda(&self, w: Vec3) -> Float {
+        if w.z == 0.0 {
+            return Float::INFINITY;
+        }
+        let x = self.alpha_x * w.x;
+        let y = self.alpha_y * w.y;
+        (((x * x + y * y) / (w.z * w.z) + 1.0).sqrt() - 1.0) / 2.0
+    }
+
+    /// The fraction of microfacets visible from a local direction
+    pub fn g1(&self, w: Vec3) -> Float {
+        1.0 / (1.0 + self.lambda(w))
+    }
+
+    /// The fraction of microfacets visible from both local directions
+    pub fn g(&self, wo: Vec3, wi: Vec3) -> Float {
+        1.0 / (1.0 + self.lambda(wo) + self.lambda(wi))
+    }
+
+    /// The density of visible microfacet normals from a local direction
+    pub fn pdf(&self, w: Vec3, wm: Vec3) -> Float {
+        if w.z == 0.0 {
+            return 0.0;
+        }
+        self.g1(w) / w.z.abs() * self.d(wm) * Vec3::dot(w, wm).abs()
+    }
+
+    /// Samples a microfacet normal visible from a local direction, with the
+    /// density given by `pdf`
+    pub fn sample_wm(&self, w: Vec3, u1: Float, u2: Float) -> Vec3 {
+        // Stretch the direction into the hemisphere configuration
+        let wh = Vec3::new(self.alpha_x * w.x, self.alpha_y * w.y, w.z).unit();
+        let wh = if wh.z < 0.0 { -wh } else { wh };
+        let t1 = if wh.z < 0.99999 {
+            Vec3::cross(Vec3::new(0.0, 0.0, 1.0), wh).unit()
+        } else {
+            Vec3::new(1.0, 0.0, 0.0)
+        };
+        let t2 = Vec3::cross(wh, t1);
+        // Sample the projected area of the visible hemisphere
+        let r   = u1.sqrt();
+        let phi = 2.0 * PI * u2;
+        let px  = r * phi.cos();
+        let py  = r * phi.sin();
+        let h   = (1.0 - px * px).sqrt();
+        let s   = (1.0 + wh.z) / 2.0;
+        let py  = (1.0 - s) * h + s * py;
+        let pz  = (1.0 - px * px - py * py).max(0.0).sqrt();
+        // Reproject onto the hemisphere and unstretch
+        let nh = px * t1 + py * t2 + pz * wh;
+        Vec3::new(self.alpha_x * nh.x, self.alpha_y * nh.y, nh.z.max(1e-6)).unit()
+    }
+}
+
+/// Returns the Fresnel reflectance of a dielectric interface
+///
+/// # Arguments
+/// * `cos_i` - the cosine of the incident angle, negative from inside
+/// * `eta`   - the ratio of the inside and outside indices of refraction
+///
+pub fn fresnel_dielectric(cos_i: Float, eta: Float) -> Float {
+    let (cos_i, eta) = if cos_i < 0.0 { (-cos_i, 1.0 / eta) } else { (cos_i, eta) };
+    let cos_i = cos_i.min(1.0);
+    let sin2_t = (1.0 - cos_i * cos_i) / (eta * eta);
+    if sin2_t >= 1.0 {
+        return 1.0;
+    }
+    let cos_t  = (1.0 - sin2_t).max(0.0).sqrt();
+    let r_parl = (eta * cos_i - cos_t) / (eta * cos_i + cos_t);
+    let r_perp = (cos_i - eta * cos_t) / (cos_i + eta * cos_t);
+    (r_parl * r_parl + r_perp * r_perp) / 2.0
+}
+
+/// Returns the Fresnel reflectance of a conductor per color channel
+///
+/// # Arguments
+/// * `cos_i` - the cosine of the incident angle
+/// * `eta`   - the real part of the conductor's index of refraction
+/// * `k`     - the imaginary part, its absorption coefficient
+///
+pub fn fresnel_conductor(cos_i: Float, eta: Vec3, k: Vec3) -> Vec3 {
+    let channel = |eta: Float, k: Float| {
+        let cos2 = cos_i.max(0.0).min(1.0).powi(2);
+        let sin2 = 1.0 - cos2;
+        let t0 = eta * eta - k * k - sin2;
+        let a2_plus_b2 = (t0 * t0 + 4.0 * eta * eta * k * k).sqrt();
+        let t1 = a2_plus_b2 + cos2;
+        let a  = (0.5 * (a2_plus_b2 + t0)).max(0.0).sqrt();
+        let t2 = 2.0 * cos2.sqrt() * a;
+        let rs = (t1 - t2) / (t1 + t2);
+        let t3 = cos2 * a2_plus_b2 + sin2 * sin2;
+        let t4 = t2 * sin2;
+        let rp = rs * (t3 - t4) / (t3 + t4);
+        0.5 * (rp + rs)
+    };
+    Vec3::new(channel(eta.x, k.x), channel(eta.y, k.y), channel(eta.z, k.z))
+}
+
+/// Reflects a direction pointing away from a surface about a normal
+pub fn reflect(wo: Vec3, n: Vec3) -> Vec3 {
+    -wo + 2.0 * Vec3::dot(wo, n) * n
+}
+
+/// Refracts a direction pointing away from a surface through a normal,
+/// returning the refracted direction and the relative index of refraction
+/// along the path, or None on total internal reflection
+///
+/// # Arguments
+/// * `wi`  - the direction pointing away from the surface
+/// * `n`   - the normal
+/// * `eta` - the ratio of the indices of refraction below and above `n`
+///
+pub fn refract(wi: Vec3, n: Vec3, eta: Float) -> Option<(Vec3, Float)> {
+    let cos_i = Vec3::dot(n, wi);
+    let (n, eta, cos_i) = if cos_i < 0.0 { (-n, 1.0 / eta, -cos_i) } else { (n, eta, cos_i) };
+    let sin2_t = (1.0 - cos_i * cos_i).max(0.0) / (eta * eta);
+    if sin2_t >= 1.0 {
+        return None;
+    }
+    let cos_t = (1.0 - sin2_t).sqrt();
+    Some((-wi / eta + (cos_i / eta - cos_t) * n, eta))
+}
+
+/// Evaluates a rough dielectric BSDF between two local directions, returning
+/// the BSDF times the cosine term and the density of sampling `wi`
+///
+/// # Arguments
+/// * `mfd` - the microfacet distribution
+/// * `eta` - the ratio of the inside and outside indices of refraction
+/// * `wo`  - the outgoing direction, towards the viewer
+/// * `wi`  - the incident direction, towards the light
+///
+pub fn rough_dielectric(mfd: TrowbridgeReitz, eta: Float, wo: Vec3, wi: Vec3) -> (Float, Float) {
+    let (cos_o, cos_i) = (wo.z, wi.z);
+    if mfd.is_smooth() || cos_o == 0.0 || cos_i == 0.0 {
+        return (0.0, 0.0);
+    }
+    // Find the generalized half vector
+    let reflected = cos_o * cos_i > 0.0;
+    let etap = if reflected { 1.0 } else if cos_o > 0.0 { eta } else { 1.0 / eta };
+    let wm = wi * etap + wo;
+    if wm.mag() == 0.0 {
+        return (0.0, 0.0);
+    }
+    let wm = wm.unit();
+    let wm = if wm.z < 0.0 { -wm } else { wm };
+    // Discard back facing microfacets
+    let (dot_i, dot_o) = (Vec3::dot(wm, wi), Vec3::dot(wm, wo));
+    if dot_i * cos_i < 0.0 || dot_o * cos_o < 0.0 {
+        return (0.0, 0.0);
+    }
+    let r = fresnel_dielectric(dot_o, eta);
+    let t = 1.0 - r;
+    if reflected {
+        let f   = mfd.d(wm) * mfd.g(wo, wi) * r / (4.0 * cos_i * cos_o).abs();
+        let pdf = mfd.pdf(wo, wm) / (4.0 * dot_o.abs()) * r;
+        (f * cos_i.abs(), pdf)
+    } else {
+        let denom = (dot_i + dot_o / etap).powi(2);
+        let f   = mfd.d(wm) * t * mfd.g(wo, wi)
+            * (dot_i * dot_o / (denom * cos_i * cos_o)).abs();
+        let pdf = mfd.pdf(wo, wm) * dot_i.abs() / denom * t;
+        (f * cos_i.abs(), pdf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ETA: Float = 1.5;
+
+    /// Returns the unit direction at an angle from the z axis in the xz plane
+    fn at_angle(theta: Float) -> Vec3 {
+        Vec3::new(theta.sin(), 0.0, theta.cos())
+    }
+
+    fn close(a: Vec3, b: Vec3) -> bool {
+        (a - b).mag() < 1e-5
+    }
+
+    #[test]
+    fn refract_passes_straight_through_at_normal_incidence() {
+        let n = Vec3::new(0.0, 0.0, 1.0);
+        let (wt, eta) = refract(n, n, ETA).unwrap();
+        assert!(close(wt, -n));
+        assert_eq!(eta, ETA);
+    }
+
+    #[test]
+    fn refract_obeys_snells_law() {
+        let n = Vec3::new(0.0, 0.0, 1.0);
+        for &theta in &[0.1, 0.5, 1.0, 1.4] {
+            let wi = at_angle(theta);
+            let (wt, _) = refract(wi, n, ETA).unwrap();
+            assert!((wt.mag() - 1.0).abs() < 1e-5);
+            assert!(wt.z < 0.0);
+            assert!(wt.x < 0.0);
+            let sin_t = (wt.x * wt.x + wt.y * wt.y).sqrt();
+            assert!((ETA * sin_t - theta.sin()).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn refract_flips_the_normal_from_inside() {
+        let n = Vec3::new(0.0, 0.0, 1.0);
+        let wi = -at_angle(0.3);
+        let (wt, eta) = refract(wi, n, ETA).unwrap();
+        assert_eq!(eta, 1.0 / ETA);
+        assert!(wt.z > 0.0);
+        // Refraction is reversible
+        let (back, _) = refract(wt, n, ETA).unwrap();
+        assert!(close(back, wi));
+    }
+
+    #[test]
+    fn refract_fails_past_the_critical_angle() {
+        let n = Vec3::new(0.0, 0.0, 1.0);
+        let critical = (1.0 / ETA).asin();
+        assert!(refract(-at_angle(critical - 0.01), n, ETA).is_some());
+        assert!(refract(-at_angle(critical + 0.01), n, ETA).is_none());
+        assert!(refract(at_angle(critical + 0.01), n, ETA).is_some());
+    }
+
+    #[test]
+    fn fresnel_dielectric_at_normal_incidence() {
+        let expected = ((ETA - 1.0) / (ETA + 1.0)).powi(2);
+        assert!((fresnel_dielectric(1.0, ETA) - expected).abs() < 1e-6);
+        assert!((fresnel_dielectric(-1.0, ETA) - expected).abs() < 1e-6);
+        assert!(fresnel_dielectric(1.0, 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn fresnel_dielectric_is_total_at_grazing_and_past_the_critical_angle() {
+        assert!((fresnel_dielectric(0.0, ETA) - 1.0).abs() < 1e-6);
+        let critical = (1.0 / ETA).asin();
+        assert_eq!(fresnel_dielectric(-(critical + 0.01).cos(), ETA), 1.0);
+        assert!(fresnel_dielectric(-(critical - 0.01).cos(), ETA) < 1.0);
+    }
+
+    #[test]
+    fn fresnel_dielectric_is_symmetric_across_the_interface() {
+        let n = Vec3::new(0.0, 0.0, 1.0);
+        for &theta in &[0.1, 0.5, 1.0, 1.4] {
+            let (wt, _) = refract(at_angle(theta), n, ETA).unwrap();
+            let outside = fresnel_dielectric(theta.cos(), ETA);
+            let inside = fresnel_dielectric(wt.z, ETA);
+            assert!(outside > 0.0 && outside < 1.0);
+            assert!((outside - inside).abs() < 1e-5);
+        }
+    }
+}
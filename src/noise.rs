@@ -1,4 +1,4 @@
-use util::rand;
+use util::{rand, Float, PI};
 use vec3::Vec3;
 
 #[derive(Debug, Clone)]
@@ -6,50 +6,168 @@ pub struct Perlin {
     perm_x: Vec<usize>,
     perm_y: Vec<usize>,
     perm_z: Vec<usize>,
-    rand_float: Vec<f64>,
+    /// Random unit gradients at the lattice points
+    gradients: Vec<Vec3>,
 }
 
 impl Perlin {
+    /// Creates a new randomly generated noise function
     pub fn new() -> Self {
+        Perlin::generate(&mut rand)
+    }
+
+    /// Creates a new noise function which is the same for the same seed
+    pub fn seeded(seed: u64) -> Self {
+        let mut state = seed;
+        Perlin::generate(&mut || splitmix(&mut state))
+    }
+
+    /// Generates the lattice from a source of random floats in [0, 1)
+    fn generate<R: FnMut() -> Float>(rng: &mut R) -> Self {
+        let gradients = (0..256)
+            .map(|_| {
+                let z = 1.0 - 2.0 * rng();
+                let phi = 2.0 * PI * rng();
+                let r = (1.0 - z * z).max(0.0).sqrt();
+                Vec3::new(r * phi.cos(), r * phi.sin(), z)
+            })
+            .collect();
         Perlin {
-            perm_x: perlin_generate_perm(),
-            perm_y: perlin_generate_perm(),
-            perm_z: perlin_generate_perm(),
-            rand_float: perlin_generate(),
+            perm_x: perlin_generate_perm(rng),
+            perm_y: perlin_generate_perm(rng),
+            perm_z: perlin_generate_perm(rng),
+            gradients: gradients,
         }
     }
 
-    pub fn noise(&self, point: Vec3) -> f64 {
-        // let u = point.x - point.x.floor();
-        // let v = point.y - point.y.floor();
-        // let w = point.z - point.z.floor();
-        let i = ((4.0 * point.x) as usize) & 255;
-        let j = ((4.0 * point.y) as usize) & 255;
-        let k = ((4.0 * point.z) as usize) & 255;
-        self.rand_float[self.perm_x[i] ^ self.perm_y[j] ^ self.perm_z[k]]
+    /// Returns the gradient noise at a point, in roughly [-1, 1]. The
+    /// gradients at the surrounding lattice points are blended with quintic
+    /// smoothing, so the noise is continuous up to its second derivative.
+    pub fn noise(&self, point: Vec3) -> Float {
+        let (fx, fy, fz) = (point.x.floor(), point.y.floor(), point.z.floor());
+        let (u, v, w) = (point.x - fx, point.y - fy, point.z - fz);
+        let (i, j, k) = (fx as i64, fy as i64, fz as i64);
+        let mut sum = 0.0;
+        for di in 0..2 {
+            for dj in 0..2 {
+                for dk in 0..2 {
+                    let gradient = self.gradients[
+                        self.perm_x[((i + di) & 255) as usize]
+                        ^ self.perm_y[((j + dj) & 255) as usize]
+                        ^ self.perm_z[((k + dk) & 255) as usize]
+                    ];
+                    let (di, dj, dk) = (di as Float, dj as Float, dk as Float);
+                    let offset = Vec3::new(u - di, v - dj, w - dk);
+                    let weight = (di * fade(u) + (1.0 - di) * (1.0 - fade(u)))
+                               * (dj * fade(v) + (1.0 - dj) * (1.0 - fade(v)))
+                               * (dk * fade(w) + (1.0 - dk) * (1.0 - fade(w)));
+                    sum += weight * Vec3::dot(gradient, offset);
+                }
+            }
+        }
+        sum
     }
-}
 
-fn perlin_generate() -> Vec<f64> {
-    let mut v = Vec::new();
-    for _ in 0..256 {
-        v.push(rand());
+    /// Returns fractal turbulence at a point, summing the magnitude of the
+    /// noise over octaves of doubling frequency and halving amplitude
+    ///
+    /// # Arguments
+    /// * `point`   - the point
+    /// * `octaves` - the number of octaves summed
+    ///
+    pub fn turbulence(&self, point: Vec3, octaves: u32) -> Float {
+        let mut sum = 0.0;
+        let mut point = point;
+        let mut weight = 1.0;
+        for _ in 0..octaves {
+            sum += weight * self.noise(point).abs();
+            weight *= 0.5;
+            point = point * 2.0;
+        }
+        sum
     }
-    v
 }
 
-fn perlin_generate_perm() -> Vec<usize> {
+/// The quintic smoothing curve 6t^5 - 15t^4 + 10t^3
+fn fade(t: Float) -> Float {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+/// Steps the SplitMix64 generator, returning a float in [0, 1)
+fn splitmix(state: &mut u64) -> Float {
+    *state = state.wrapping_add(0x9E3779B97F4A7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z = z ^ (z >> 31);
+    ((z >> 11) as f64 / (1u64 << 53) as f64) as Float
+}
+
+fn perlin_generate_perm<R: FnMut() -> Float>(rng: &mut R) -> Vec<usize> {
     let mut v = Vec::new();
     for i in 0..256 {
         v.push(i as usize);
     }
-    permute(&mut v, 256);
+    permute(&mut v, 256, rng);
     v
 }
 
-fn permute(v: &mut Vec<usize>, n: i32) {
+fn permute<R: FnMut() -> Float>(v: &mut Vec<usize>, n: i32, rng: &mut R) {
     for i in (1..n).rev() {
-        let target = (rand() * ((i as f64) + 1.0)) as usize;
+        let target = ((rng() * ((i as Float) + 1.0)) as usize).min(i as usize);
         v.swap(i as usize, target);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Lattice coordinates on either side of and at zero
+    const LATTICE: [Float; 5] = [-2.0, -1.0, 0.0, 1.0, 3.0];
+
+    /// An off lattice coordinate
+    const OFF: Float = 0.37;
+
+    #[test]
+    fn noise_is_zero_at_lattice_points() {
+        let perlin = Perlin::seeded(1);
+        for &x in &LATTICE {
+            for &y in &LATTICE {
+                for &z in &LATTICE {
+                    assert!(perlin.noise(Vec3::new(x, y, z)).abs() < 1e-6);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn noise_is_continuous_across_lattice_planes() {
+        let perlin = Perlin::seeded(2);
+        let eps = 1e-4;
+        for &k in &LATTICE {
+            for axis in 0..3 {
+                let point = |k: Float| Vec3::from_axes(axis, k, OFF, -OFF);
+                let below = perlin.noise(point(k - eps));
+                let above = perlin.noise(point(k + eps));
+                assert!(
+                    (above - below).abs() < 1e-2,
+                    "noise jumps from {} to {} across {} on axis {}",
+                    below, above, k, axis,
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn seeded_noise_is_deterministic() {
+        let (a, b, c) = (Perlin::seeded(7), Perlin::seeded(7), Perlin::seeded(8));
+        let points: Vec<Vec3> = (0..32)
+            .map(|n| Vec3::new(n as Float * 0.731 - 11.0, n as Float * -0.293, 4.1))
+            .collect();
+        for &p in &points {
+            assert_eq!(a.noise(p), b.noise(p));
+        }
+        assert!(points.iter().any(|&p| a.noise(p) != c.noise(p)));
+    }
+}
@@ -0,0 +1,321 @@
+use util::{rand, schlick, Float, PI};
+use vec3::Vec3;
+use geo::Intersection;
+use texture::Texture;
+use spectrum::reflectance;
+use microfacet::{
+    Frame,
+    TrowbridgeReitz,
+    reflect,
+    refract,
+    fresnel_dielectric,
+    rough_dielectric,
+};
+
+/// The smallest roughness used by the principled BSDF, which keeps every
+/// lobe rough enough to be evaluated and light sampled
+const MIN_ROUGHNESS: Float = 0.05;
+
+#[derive(Debug, Clone)]
+pub struct Param {
+    /// The constant factor of the parameter
+    pub factor: Float,
+    /// An optional texture and the channel of it that scales the factor
+    pub texture: Option<(Texture, u32)>,
+}
+
+impl Param {
+    /// Creates a new constant parameter
+    pub fn constant(factor: Float) -> Self {
+        Param { factor, texture: None }
+    }
+
+    /// Creates a new parameter read from one channel of a texture and scaled
+    /// by a factor
+    pub fn texture(texture: Texture, channel: u32, factor: Float) -> Self {
+        Param { factor, texture: Some((texture, channel)) }
+    }
+
+    /// Gets the value of the parameter at a given intersection
+    pub fn value(&self, i: &Intersection) -> Float {
+        match self.texture {
+            Some((ref texture, channel)) => self.factor * texture.value(i)[channel],
+            None => self.factor,
+        }
+    }
+}
+
+/// The parameters of the Disney principled BSDF, a single material blending
+/// diffuse, sheen, specular, clearcoat and transmission lobes
+#[derive(Debug, Clone)]
+pub struct Principled {
+    /// The surface color, the diffuse albedo or the metallic reflectance
+    pub base_color: Texture,
+    /// Blends from a dielectric to a metallic surface
+    pub metallic: Param,
+    /// The perceptual roughness of the specular and transmission lobes
+    pub roughness: Param,
+    /// The dielectric specular strength, 0.5 is an index of refraction of 1.5
+    pub specular: Param,
+    /// Tints the dielectric specular towards the base color
+    pub specular_tint: Param,
+    /// Stretches the specular highlight along the surface tangent
+    pub anisotropic: Param,
+    /// The strength of the retroreflective sheen at grazing angles
+    pub sheen: Param,
+    /// Tints the sheen towards the base color
+    pub sheen_tint: Param,
+    /// The strength of a second, white specular lobe
+    pub clearcoat: Param,
+    /// The glossiness of the clearcoat lobe
+    pub clearcoat_gloss: Param,
+    /// Blends from an opaque to a fully transmissive dielectric
+    pub transmission: Param,
+}
+
+/// The principled parameters resolved at an intersection
+struct Lobes {
+    base: Vec3,
+    tint: Vec3,
+    metallic: Float,
+    roughness: Float,
+    anisotropic: Float,
+    specular: Float,
+    specular_tint: Float,
+    sheen: Float,
+    sheen_tint: Float,
+    clearcoat: Float,
+    clearcoat_gloss: Float,
+    transmission: Float,
+}
+
+impl Principled {
+    /// Creates a new principled BSDF with the given base color and the
+    /// default values of every other parameter
+    pub fn new(base_color: Texture) -> Self {
+        Principled {
+            base_color,
+            metallic:        Param::constant(0.0),
+            roughness:       Param::constant(0.5),
+            specular:        Param::constant(0.5),
+            specular_tint:   Param::constant(0.0),
+            anisotropic:     Param::constant(0.0),
+            sheen:           Param::constant(0.0),
+            sheen_tint:      Param::constant(0.5),
+            clearcoat:       Param::constant(0.0),
+            clearcoat_gloss: Param::constant(1.0),
+            transmission:    Param::constant(0.0),
+        }
+    }
+
+    /// Creates a new principled BSDF from a glTF metallic-roughness material.
+    /// glTF packs roughness into the green and metalness into the blue
+    /// channel of its metallic-roughness texture.
+    ///
+    /// # Arguments
+    /// * `base_color_factor`  - the linear base color factor
+    /// * `base_color`         - the optional base color texture
+    /// * `metallic_factor`    - the metalness factor
+    /// * `roughness_factor`   - the roughness factor
+    /// * `metallic_roughness` - the optional metallic-roughness texture
+    ///
+    pub fn gltf(
+        base_color_factor: Vec3,
+        base_color: Option<Texture>,
+        metallic_factor: Float,
+        roughness_factor: Float,
+        metallic_roughness: Option<Texture>,
+    ) -> Self {
+        let base_color = match base_color {
+            Some(texture) => Texture::scale(texture, base_color_factor),
+            None => Texture::solid(base_color_factor),
+        };
+        let (metallic, roughness) = match metallic_roughness {
+            Some(texture) => (
+                Param::texture(texture.clone(), 2, metallic_factor),
+                Param::texture(texture, 1, roughness_factor),
+            ),
+            None => (
+                Param::constant(metallic_factor),
+                Param::constant(roughness_factor),
+            ),
+        };
+        Principled { metallic, roughness, ..Principled::new(base_color) }
+    }
+
+    /// Resolves every parameter at a given intersection, with the colors
+    /// upsampled at the wavelengths of a spectral path
+    fn lobes(&self, i: &Intersection, wavelengths: Option<Vec3>) -> Lobes {
+        let clamp = |x: Float| x.max(0.0).min(1.0);
+        // The base color normalized by its luminance, giving its hue
+        let base = self.base_color.value(i);
+        let luminance = base.luminance();
+        let tint = if luminance > 0.0 { base / luminance } else { Vec3::ones() };
+        Lobes {
+            base:            reflectance(base, wavelengths),
+            tint:            reflectance(tint, wavelengths),
+            metallic:        clamp(self.metallic.value(i)),
+            roughness:       clamp(self.roughness.value(i)).max(MIN_ROUGHNESS),
+            anisotropic:     clamp(self.anisotropic.value(i)),
+            specular:        clamp(self.specular.value(i)),
+            specular_tint:   clamp(self.specular_tint.value(i)),
+            sheen:           clamp(self.sheen.value(i)),
+            sheen_tint:      clamp(self.sheen_tint.value(i)),
+            clearcoat:       clamp(self.clearcoat.value(i)),
+            clearcoat_gloss: clamp(self.clearcoat_gloss.value(i)),
+            transmission:    clamp(self.transmission.value(i)),
+        }
+    }
+
+    /// Evaluates the BSDF times the cosine term for light leaving the
+    /// intersection in the given direction, along with the density with
+    /// which `sample` scatters into it
+    ///
+    /// # Arguments
+    /// * `i`           - the intersection
+    /// * `dir`         - the direction light leaves in
+    /// * `wavelengths` - the wavelengths traced by a spectral path, None when
+    ///                   tracing in RGB
+    ///
+    pub fn eval(&self, i: &Intersection, dir: Vec3, wavelengths: Option<Vec3>) -> (Vec3, Float) {
+        let frame = Frame::from_tangent(i.normal, i.dpdu);
+        let wo = frame.to_local(-i.ray.dir);
+        let wi = frame.to_local(dir.unit());
+        self.lobes(i, wavelengths).eval(wo, wi)
+    }
+
+    /// Samples a direction for light leaving the intersection, returning
+    /// None if the sampled lobe does not scatter
+    pub fn sample(&self, i: &Intersection) -> Option<Vec3> {
+        let frame = Frame::from_tangent(i.normal, i.dpdu);
+        let wo = frame.to_local(-i.ray.dir);
+        // Lobes are chosen by their scalar weights alone, so no colors need
+        // upsampling
+        self.lobes(i, None).sample(wo).map(|wi| frame.from_local(wi))
+    }
+}
+
+impl Lobes {
+    /// The weights of the diffuse, specular, clearcoat and glass lobes
+    fn weights(&self) -> (Float, Float, Float, Float) {
+        let dielectric = 1.0 - self.metallic;
+        (
+            dielectric * (1.0 - self.transmission),
+            self.metallic + dielectric * (1.0 - self.transmission),
+            0.25 * self.clearcoat,
+            dielectric * self.transmission,
+        )
+    }
+
+    /// The specular distribution, anisotropic along the surface tangent in u
+    fn specular_mfd(&self) -> TrowbridgeReitz {
+        let aspect = (1.0 - 0.9 * self.anisotropic).sqrt().sqrt();
+        TrowbridgeReitz::anisotropic(self.roughness / aspect, self.roughness * aspect)
+    }
+
+    /// The relative index of refraction implied by the specular strength
+    fn eta(&self) -> Float {
+        let r = (0.08 * self.specular).sqrt().min(0.99);
+        (1.0 + r) / (1.0 - r)
+    }
+
+    /// The alpha of the clearcoat distribution
+    fn clearcoat_alpha(&self) -> Float {
+        0.1 + (0.001 - 0.1) * self.clearcoat_gloss
+    }
+
+    fn eval(&self, wo: Vec3, wi: Vec3) -> (Vec3, Float) {
+        let (w_diffuse, w_specular, w_clearcoat, w_glass) = self.weights();
+        let total = w_diffuse + w_specular + w_clearcoat + w_glass;
+        let mut f = Vec3::zeros();
+        let mut pdf = 0.0;
+        // The glass lobe depends on the side of the surface being viewed from
+        if w_glass > 0.0 {
+            let (glass_f, glass_pdf) = rough_dielectric(self.specular_mfd(), self.eta(), wo, wi);
+            let tint = if wo.z * wi.z < 0.0 {
+                Vec3::new(self.base.x.sqrt(), self.base.y.sqrt(), self.base.z.sqrt())
+            } else {
+                Vec3::ones()
+            };
+            f = f + tint * glass_f * w_glass;
+            pdf += glass_pdf * w_glass / total;
+        }
+        // Every other lobe is two sided and only reflects
+        let (wo, wi) = if wo.z < 0.0 {
+            (Vec3::new(wo.x, wo.y, -wo.z), Vec3::new(wi.x, wi.y, -wi.z))
+        } else {
+            (wo, wi)
+        };
+        if wo.z <= 0.0 || wi.z <= 0.0 {
+            return (f, pdf);
+        }
+        let wh = (wo + wi).unit();
+        let cos_d = Vec3::dot(wi, wh);
+        let fresnel = |cosine: Float| (1.0 - cosine).max(0.0).powi(5);
+        // Burley diffuse with a retroreflective sheen
+        let fd90 = 0.5 + 2.0 * self.roughness * cos_d * cos_d;
+        let fd = (1.0 + (fd90 - 1.0) * fresnel(wi.z)) * (1.0 + (fd90 - 1.0) * fresnel(wo.z));
+        let sheen = (Vec3::ones() + (self.tint - Vec3::ones()) * self.sheen_tint)
+            * self.sheen * fresnel(cos_d);
+        f = f + (self.base * fd / PI + sheen) * wi.z * w_diffuse;
+        pdf += wi.z / PI * w_diffuse / total;
+        // Specular reflection tinted towards the base color for metals
+        let mfd = self.specular_mfd();
+        let dielectric = (Vec3::ones() + (self.tint - Vec3::ones()) * self.specular_tint)
+            * 0.08 * self.specular;
+        let f0 = dielectric + (self.base - dielectric) * self.metallic;
+        let specular_f = f0 + (Vec3::ones() - f0) * fresnel(cos_d);
+        f = f + specular_f * mfd.d(wh) * mfd.g(wo, wi) / (4.0 * wo.z) * w_specular;
+        pdf += mfd.pdf(wo, wh) / (4.0 * cos_d) * w_specular / total;
+        // Clearcoat with a fixed index of refraction of 1.5
+        if w_clearcoat > 0.0 {
+            let d = gtr1(wh.z, self.clearcoat_alpha());
+            let g = TrowbridgeReitz::new(0.5).g(wo, wi);
+            f = f + Vec3::ones() * schlick(cos_d, 1.5) * d * g / (4.0 * wo.z) * w_clearcoat;
+            pdf += d * wh.z / (4.0 * cos_d) * w_clearcoat / total;
+        }
+        (f, pdf)
+    }
+
+    fn sample(&self, wo: Vec3) -> Option<Vec3> {
+        let (w_diffuse, w_specular, w_clearcoat, w_glass) = self.weights();
+        let u = rand() * (w_diffuse + w_specular + w_clearcoat + w_glass);
+        // Sample the glass lobe in the geometric frame
+        if u < w_glass {
+            let mfd = self.specular_mfd();
+            let wm = mfd.sample_wm(wo, rand(), rand());
+            let reflected = rand() < fresnel_dielectric(Vec3::dot(wo, wm), self.eta());
+            let wi = if reflected {
+                reflect(wo, wm)
+            } else {
+                refract(wo, wm, self.eta())?.0
+            };
+            return if reflected == (wi.z * wo.z > 0.0) { Some(wi) } else { None };
+        }
+        // Sample the reflection lobes on the viewer's side of the surface
+        let flip = wo.z < 0.0;
+        let wo = if flip { Vec3::new(wo.x, wo.y, -wo.z) } else { wo };
+        let wi = if u < w_glass + w_diffuse {
+            (Vec3::new(0.0, 0.0, 1.0) + Vec3::rand_unit()).unit()
+        } else if u < w_glass + w_diffuse + w_specular {
+            reflect(wo, self.specular_mfd().sample_wm(wo, rand(), rand()))
+        } else {
+            let a2 = self.clearcoat_alpha().powi(2);
+            let cos_h = ((1.0 - a2.powf(1.0 - rand())) / (1.0 - a2)).max(0.0).sqrt();
+            let sin_h = (1.0 - cos_h * cos_h).max(0.0).sqrt();
+            let phi = 2.0 * PI * rand();
+            reflect(wo, Vec3::new(sin_h * phi.cos(), sin_h * phi.sin(), cos_h))
+        };
+        if wi.z <= 0.0 {
+            return None;
+        }
+        Some(if flip { Vec3::new(wi.x, wi.y, -wi.z) } else { wi })
+    }
+}
+
+/// The generalized Trowbridge-Reitz distribution with an exponent of 1, used
+/// for the long tailed clearcoat highlight
+fn gtr1(cos_h: Float, alpha: Float) -> Float {
+    let a2 = alpha * alpha;
+    (a2 - 1.0) / (PI * a2.ln() * (1.0 + (a2 - 1.0) * cos_h * cos_h))
+}
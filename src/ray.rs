@@ -1,6 +1,19 @@
+use util::Float;
 use vec3::Vec3;
 use geo::Intersection;
 
+#[derive(Debug, Clone, Copy)]
+pub struct RayDifferential {
+    /// The origin of the ray offset by one pixel in x
+    pub rx_origin: Vec3,
+    /// The direction of the ray offset by one pixel in x
+    pub rx_dir: Vec3,
+    /// The origin of the ray offset by one pixel in y
+    pub ry_origin: Vec3,
+    /// The direction of the ray offset by one pixel in y
+    pub ry_dir: Vec3,
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct Ray {
     /// The originating point of the ray
@@ -8,7 +21,13 @@ pub struct Ray {
     /// The direction of the ray
     pub dir: Vec3,
     /// The time of the ray
-    pub time: f64,
+    pub time: Float,
+    /// The single wavelength in nanometers carried by the ray, once its path
+    /// has been split by a dispersive surface
+    pub wavelength: Option<Float>,
+    /// The rays offset by one pixel on screen, tracking the footprint of the
+    /// ray across surfaces it hits
+    pub differential: Option<RayDifferential>,
 }
 
 impl Ray {
@@ -18,19 +37,87 @@ impl Ray {
     /// * `origin` - the originating point of the ray
     /// * `dir`    - the direction of the ray
     ///
-    pub fn new(origin: Vec3, dir: Vec3, time: f64) -> Self {
-        Ray { origin, dir: dir.unit(), time, }
+    pub fn new(origin: Vec3, dir: Vec3, time: Float) -> Self {
+        Ray { origin, dir: dir.unit(), time, wavelength: None, differential: None }
+    }
+
+    /// Sets the rays offset by one pixel on screen
+    pub fn with_differential(self, differential: RayDifferential) -> Self {
+        Ray { differential: Some(differential), ..self }
     }
 
     /// Creates a new Ray starting from an intersection going off in the given
-    /// direction at the same time as the incident ray.
+    /// direction at the same time and wavelength as the incident ray. The
+    /// origin is offset off the surface to avoid self-intersection.
     ///
     /// # Arguments
     /// * `i`   - the intersection
     /// * `dir` - the outgoing direction of the ray
     ///
     pub fn from_intersection(i: &Intersection, dir: Vec3) -> Self {
-        Ray::new(i.point, dir, i.ray.time)
+        Ray { wavelength: i.ray.wavelength, ..Ray::new(i.offset_point(dir), dir, i.ray.time) }
+    }
+
+    /// Creates a new Ray scattered specularly off an intersection, like
+    /// `from_intersection`, carrying the incident ray's differentials
+    /// reflected or refracted along with it so they keep tracking its
+    /// footprint.
+    ///
+    /// # Arguments
+    /// * `i`       - the intersection
+    /// * `dir`     - the outgoing direction of the ray
+    /// * `ref_idx` - the index of refraction of the surface, if the ray may
+    ///               be transmitted through it
+    ///
+    pub fn specular(i: &Intersection, dir: Vec3, ref_idx: Option<Float>) -> Self {
+        let ray = Ray::from_intersection(i, dir);
+        let differential = match i.ray.differential {
+            Some(differential) => differential,
+            None               => return ray,
+        };
+        let wo = -i.ray.dir;
+        let wi = ray.dir;
+        // Face the normal and its change across the screen towards the
+        // incident ray
+        let facing = if Vec3::dot(wo, i.normal) < 0.0 { -1.0 } else { 1.0 };
+        let n = facing * i.normal;
+        let (dpdx, dpdy, dndx, dndy) = match i.differentials {
+            Some(d) => (
+                d.dpdx,
+                d.dpdy,
+                facing * (d.dudx * i.dndu + d.dvdx * i.dndv),
+                facing * (d.dudy * i.dndu + d.dvdy * i.dndv),
+            ),
+            None => (Vec3::zeros(), Vec3::zeros(), Vec3::zeros(), Vec3::zeros()),
+        };
+        let dwodx = -differential.rx_dir - wo;
+        let dwody = -differential.ry_dir - wo;
+        let ddndx = Vec3::dot(dwodx, n) + Vec3::dot(wo, dndx);
+        let ddndy = Vec3::dot(dwody, n) + Vec3::dot(wo, dndy);
+        let (rx_dir, ry_dir) = if Vec3::dot(wi, n) > 0.0 {
+            let cos_o = Vec3::dot(wo, n);
+            (
+                wi - dwodx + 2.0 * (cos_o * dndx + ddndx * n),
+                wi - dwody + 2.0 * (cos_o * dndy + ddndy * n),
+            )
+        } else {
+            // The relative index of refraction, as in `Vec3::refract`
+            let ref_idx = ref_idx.unwrap_or(1.0);
+            let eta = if facing > 0.0 { 1.0 / ref_idx } else { ref_idx };
+            let (cos_o, cos_t) = (Vec3::dot(wo, n), -Vec3::dot(wi, n));
+            let mu = eta * cos_o - cos_t;
+            let dmu = eta - eta * eta * cos_o / cos_t;
+            (
+                wi - eta * dwodx + mu * dndx + dmu * ddndx * n,
+                wi - eta * dwody + mu * dndy + dmu * ddndy * n,
+            )
+        };
+        ray.with_differential(RayDifferential {
+            rx_origin: i.point + dpdx,
+            rx_dir,
+            ry_origin: i.point + dpdy,
+            ry_dir,
+        })
     }
 
     /// Returns the point along the ray distance t from the origin
@@ -38,7 +125,7 @@ impl Ray {
     /// # Arguments
     /// * `t` the distance along the ray
     ///
-    pub fn at(self, t: f64) -> Vec3 {
+    pub fn at(self, t: Float) -> Vec3 {
         self.origin + t * self.dir
     }
 }
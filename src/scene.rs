@@ -1,7 +1,11 @@
+use util::Float;
 use camera::Camera;
 use vec3::Vec3;
 use ray::Ray;
-use geo::Geo;
+use geo::{Geo, Intersection};
+use light::{Light, LightSample};
+use light_tree::LightTree;
+use environment::Environment;
 
 #[derive(Debug)]
 pub struct Scene {
@@ -9,20 +13,75 @@ pub struct Scene {
     pub camera: Camera,
     /// The secne's
     pub geometry: Geo,
+    /// The radiance arriving from outside the scene
+    pub environment: Environment,
+    /// The lights sampled directly when shading
+    lights: Vec<Light>,
+    /// The structure lights are chosen from when shading
+    light_tree: LightTree,
 }
 
 impl Scene {
-    /// Creates a new scene.
+    /// Creates a new scene lit by the sky gradient. Every emissive primitive
+    /// in the geometry becomes a light.
     pub fn new(camera: Camera, geometry: Geo) -> Self {
-        Scene { camera, geometry }
+        let lights: Vec<Light> = geometry.emitters().into_iter().map(Light::area).collect();
+        let light_tree = LightTree::new(&lights);
+        Scene { camera, geometry, environment: Environment::gradient(), lights, light_tree }
     }
 
-    /// Checks if a ray intersects with the scene's geometry
-    pub fn intersects(&self, ray: Ray, min: f64, max: f64) -> Option<(Vec3, Option<Ray>)> {
-        if let Some(i) = self.geometry.intersects(ray, min, max) {
-            Some(i.material.shade(&i))
-        } else {
-            None
+    /// Replaces the scene's environment, sampling it as a light if it
+    /// supports it
+    pub fn with_environment(mut self, environment: Environment) -> Self {
+        self.lights.retain(|light| match light {
+            &Light::Environment { .. } => false,
+            _ => true,
+        });
+        if environment.is_sampled() {
+            self.lights.push(Light::environment(environment.clone()));
         }
+        self.environment = environment;
+        self.light_tree  = LightTree::new(&self.lights);
+        self
+    }
+
+    /// Adds a light to the scene
+    pub fn with_light(mut self, light: Light) -> Self {
+        self.lights.push(light);
+        self.light_tree = LightTree::new(&self.lights);
+        self
+    }
+
+    /// Checks if a ray intersects with the scene's geometry
+    pub fn intersects(&self, ray: Ray, min: Float, max: Float) -> Option<Intersection> {
+        self.geometry.intersects(ray, min, max)
+    }
+
+    /// Samples a light chosen from the light tree, returning the sample and
+    /// the probability the light was chosen with, or None if the scene has no
+    /// lights
+    ///
+    /// # Arguments
+    /// * `origin`      - the point being lit
+    /// * `time`        - the time the light is sampled at
+    /// * `wavelengths` - the wavelengths traced by a spectral path, None when
+    ///                   tracing in RGB
+    ///
+    pub fn sample_light(
+        &self,
+        origin: Vec3,
+        time: Float,
+        wavelengths: Option<Vec3>,
+    ) -> Option<(LightSample, Float)> {
+        self.light_tree.sample(origin).map(|(light, chance)| {
+            (self.lights[light].sample(origin, time, wavelengths), chance)
+        })
+    }
+
+    /// Returns the solid angle density with which `sample_light` produces the
+    /// direction of a ray from its origin, including the probability of
+    /// choosing each light
+    pub fn light_pdf(&self, ray: Ray) -> Float {
+        self.light_tree.pdf(&self.lights, ray)
     }
 }
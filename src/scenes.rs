@@ -1,10 +1,18 @@
-use util::rand;
+use util::{rand, Float};
 use vec3::Vec3;
 use scene::Scene;
 use camera::Camera;
 use geo::Geo;
 use material::Material;
 use texture::Texture;
+use environment::Environment;
+use light::Light;
+use ies::IesProfile;
+use principled::{Param, Principled};
+use medium::Medium;
+use ior::Ior;
+use thin_film::ThinFilm;
+use image_map::{Filter, Wrap};
 
 pub fn empty() -> Scene {
     Scene::new(Camera::default(), Geo::list(Vec::new()))
@@ -48,7 +56,7 @@ pub fn spheres() -> Scene {
     ))
 }
 
-pub fn random_scene(aspect: f64) -> Scene {
+pub fn random_scene(aspect: Float) -> Scene {
     let mut objects = Vec::new();
     objects.push(Geo::sphere(
         Vec3::new(0.0, -1000.0, 0.0), 1000.0,
@@ -60,8 +68,8 @@ pub fn random_scene(aspect: f64) -> Scene {
     ));
     for ia in -11..11 {
         for ib in -11..11 {
-            let a = ia as f64;
-            let b = ib as f64;
+            let a = ia as Float;
+            let b = ib as Float;
             let choose_mat = rand();
             let center = Vec3::new(a + 0.9 * rand(), 0.2, b + 0.9 * rand());
             if (center - Vec3::new(4.0, 0.2, 0.0)).len() > 0.9 {
@@ -130,7 +138,7 @@ pub fn random_scene(aspect: f64) -> Scene {
     )
 }
 
-pub fn two_spheres(aspect: f64) -> Scene {
+pub fn two_spheres(aspect: Float) -> Scene {
     let from    = Vec3::new(13.0, 2.0, 3.0);
     let at      = Vec3::zeros();
     let vup     = Vec3::new(0.0, 1.0, 0.0);
@@ -156,7 +164,7 @@ pub fn two_spheres(aspect: f64) -> Scene {
     )
 }
 
-pub fn two_perlin_spheres(aspect: f64) -> Scene {
+pub fn two_perlin_spheres(aspect: Float) -> Scene {
     let from    = Vec3::new(13.0, 2.0, 3.0);
     let at      = Vec3::zeros();
     let vup     = Vec3::new(0.0, 1.0, 0.0);
@@ -175,4 +183,653 @@ pub fn two_perlin_spheres(aspect: f64) -> Scene {
             )
         ])
     )
-}
\ No newline at end of file
+}
+pub fn simple_light(aspect: Float) -> Scene {
+    let from    = Vec3::new(26.0, 3.0, 6.0);
+    let at      = Vec3::new(0.0, 2.0, 0.0);
+    let vup     = Vec3::new(0.0, 1.0, 0.0);
+    let vfov    = 20.0;
+    let light   = Material::diffuse_light(Texture::solid(Vec3::new(4.0, 4.0, 4.0)));
+    Scene::new(
+        Camera::new(from, at, vup, vfov, aspect, None, None),
+        Geo::bvh_node(vec![
+            Geo::sphere(
+                Vec3::new(0.0, -1000.0, 0.0), 1000.0,
+                Material::lambertian(Texture::noise()),
+            ),
+            Geo::sphere(
+                Vec3::new(0.0, 2.0, 0.0), 2.0,
+                Material::lambertian(Texture::noise()),
+            ),
+            Geo::sphere(Vec3::new(0.0, 7.0, 0.0), 2.0, light.clone()),
+            Geo::xy_rect(3.0, 5.0, 1.0, 3.0, -2.0, light),
+        ])
+    ).with_environment(Environment::black())
+}
+
+pub fn cornell_box(aspect: Float) -> Scene {
+    let from    = Vec3::new(278.0, 278.0, -800.0);
+    let at      = Vec3::new(278.0, 278.0, 0.0);
+    let vup     = Vec3::new(0.0, 1.0, 0.0);
+    let vfov    = 40.0;
+    let red     = Material::lambertian(Texture::solid(Vec3::new(0.65, 0.05, 0.05)));
+    let white   = Material::lambertian(Texture::solid(Vec3::new(0.73, 0.73, 0.73)));
+    let green   = Material::lambertian(Texture::solid(Vec3::new(0.12, 0.45, 0.15)));
+    let light   = Material::diffuse_light(Texture::solid(Vec3::new(15.0, 15.0, 15.0)));
+    Scene::new(
+        Camera::new(from, at, vup, vfov, aspect, None, None),
+        Geo::list(vec![
+            Geo::yz_rect(0.0, 555.0, 0.0, 555.0, 555.0, green),
+            Geo::yz_rect(0.0, 555.0, 0.0, 555.0, 0.0, red),
+            Geo::xz_rect(213.0, 343.0, 227.0, 332.0, 554.0, light),
+            Geo::xz_rect(0.0, 555.0, 0.0, 555.0, 555.0, white.clone()),
+            Geo::xz_rect(0.0, 555.0, 0.0, 555.0, 0.0, white.clone()),
+            Geo::xy_rect(0.0, 555.0, 0.0, 555.0, 555.0, white.clone()),
+            Geo::sphere(Vec3::new(190.0, 90.0, 190.0), 90.0, white),
+            Geo::sphere(
+                Vec3::new(370.0, 120.0, 370.0), 120.0,
+                Material::dielectric(Texture::solid(Vec3::ones()), 1.5),
+            ),
+        ])
+    ).with_environment(Environment::black())
+}
+
+pub fn analytic_lights(aspect: Float) -> Scene {
+    let from    = Vec3::new(13.0, 4.0, 3.0);
+    let at      = Vec3::new(0.0, 1.0, 0.0);
+    let vup     = Vec3::new(0.0, 1.0, 0.0);
+    let vfov    = 30.0;
+    Scene::new(
+        Camera::new(from, at, vup, vfov, aspect, None, None),
+        Geo::bvh_node(vec![
+            Geo::sphere(
+                Vec3::new(0.0, -1000.0, 0.0), 1000.0,
+                Material::lambertian(Texture::solid(Vec3::new(0.5, 0.5, 0.5))),
+            ),
+            Geo::sphere(
+                Vec3::new(-3.0, 1.0, 0.0), 1.0,
+                Material::lambertian(Texture::solid(Vec3::new(0.8, 0.3, 0.3))),
+            ),
+            Geo::sphere(
+                Vec3::new(0.0, 1.0, 0.0), 1.0,
+                Material::metal(Texture::solid(Vec3::new(0.8, 0.6, 0.3)), 0.3),
+            ),
+            Geo::sphere(
+                Vec3::new(3.0, 1.0, 0.0), 1.0,
+                Material::lambertian(Texture::solid(Vec3::new(0.3, 0.3, 0.8))),
+            ),
+        ])
+    )
+    .with_environment(Environment::black())
+    .with_light(Light::point(
+        Vec3::new(-3.0, 4.0, 3.0),
+        Vec3::new(20.0, 20.0, 20.0),
+    ))
+    .with_light(Light::spot(
+        Vec3::new(3.0, 6.0, 2.0),
+        Vec3::new(3.0, 0.0, 0.0),
+        Vec3::new(60.0, 50.0, 40.0),
+        15.0,
+        25.0,
+    ))
+    .with_light(Light::directional(
+        Vec3::new(-1.0, -2.0, -1.0),
+        Vec3::new(0.2, 0.2, 0.3),
+    ))
+}
+
+pub fn light_panels(aspect: Float) -> Scene {
+    let from    = Vec3::new(0.0, 3.0, 12.0);
+    let at      = Vec3::new(0.0, 1.0, 0.0);
+    let vup     = Vec3::new(0.0, 1.0, 0.0);
+    let vfov    = 40.0;
+    let mut objects = vec![
+        Geo::sphere(
+            Vec3::new(0.0, -1000.0, 0.0), 1000.0,
+            Material::lambertian(Texture::solid(Vec3::new(0.5, 0.5, 0.5))),
+        ),
+        Geo::sphere(
+            Vec3::new(-1.5, 1.0, 0.0), 1.0,
+            Material::lambertian(Texture::solid(Vec3::new(0.8, 0.8, 0.8))),
+        ),
+        Geo::sphere(
+            Vec3::new(1.5, 1.0, 0.0), 1.0,
+            Material::metal(Texture::solid(Vec3::new(0.8, 0.8, 0.8)), 0.2),
+        ),
+    ];
+    // A wall of small LED panels, each made of two emissive triangles
+    for ix in -16..16 {
+        for iy in 0..16 {
+            let x = (ix as Float) * 0.25;
+            let y = (iy as Float) * 0.25 + 0.5;
+            let z = -3.0;
+            let emit = Material::diffuse_light(Texture::solid(Vec3::new(
+                4.0 * rand(),
+                4.0 * rand(),
+                4.0 * rand(),
+            )));
+            let (v0, v1) = (Vec3::new(x, y, z), Vec3::new(x + 0.2, y, z));
+            let (v2, v3) = (Vec3::new(x + 0.2, y + 0.2, z), Vec3::new(x, y + 0.2, z));
+            objects.push(Geo::triangle(v0, v1, v2, emit.clone()));
+            objects.push(Geo::triangle(v0, v2, v3, emit));
+        }
+    }
+    Scene::new(
+        Camera::new(from, at, vup, vfov, aspect, None, None),
+        Geo::bvh_node(objects),
+    ).with_environment(Environment::black())
+}
+
+pub fn ies_light(aspect: Float, ies: IesProfile) -> Scene {
+    let from    = Vec3::new(0.0, 2.0, 10.0);
+    let at      = Vec3::new(0.0, 1.5, 0.0);
+    let vup     = Vec3::new(0.0, 1.0, 0.0);
+    let vfov    = 40.0;
+    let white   = Material::lambertian(Texture::solid(Vec3::new(0.73, 0.73, 0.73)));
+    Scene::new(
+        Camera::new(from, at, vup, vfov, aspect, None, None),
+        Geo::list(vec![
+            Geo::xz_rect(-10.0, 10.0, -10.0, 10.0, 0.0, white.clone()),
+            Geo::xy_rect(-10.0, 10.0, 0.0, 10.0, -2.0, white),
+            Geo::sphere(
+                Vec3::new(0.0, 0.5, 0.0), 0.5,
+                Material::metal(Texture::solid(Vec3::new(0.8, 0.6, 0.3)), 0.3),
+            ),
+        ])
+    )
+    .with_environment(Environment::black())
+    // Candela values are in the thousands, scale them into scene units
+    .with_light(Light::point(
+        Vec3::new(0.0, 4.0, -1.0),
+        Vec3::new(0.01, 0.01, 0.01),
+    ).with_profile(ies))
+}
+
+pub fn microfacet(aspect: Float) -> Scene {
+    let from    = Vec3::new(0.0, 3.0, 12.0);
+    let at      = Vec3::new(0.0, 1.0, 0.0);
+    let vup     = Vec3::new(0.0, 1.0, 0.0);
+    let vfov    = 30.0;
+    Scene::new(
+        Camera::new(from, at, vup, vfov, aspect, None, None),
+        Geo::bvh_node(vec![
+            Geo::sphere(
+                Vec3::new(0.0, -1000.0, 0.0), 1000.0,
+                Material::lambertian(Texture::checker(
+                    Texture::solid(Vec3::new(0.2, 0.3, 0.1)),
+                    Texture::solid(Vec3::new(0.9, 0.9, 0.9)),
+                    10.0,
+                )),
+            ),
+            Geo::sphere(Vec3::new(-3.3, 1.0, 0.0), 1.0, Material::gold(0.2)),
+            Geo::sphere(Vec3::new(-1.1, 1.0, 0.0), 1.0, Material::copper(0.4)),
+            Geo::sphere(Vec3::new(1.1, 1.0, 0.0), 1.0, Material::aluminium(0.1)),
+            Geo::sphere(
+                Vec3::new(3.3, 1.0, 0.0), 1.0,
+                Material::rough_dielectric(Texture::solid(Vec3::new(1.0, 1.0, 1.0)), 1.5, 0.3),
+            ),
+            Geo::sphere(
+                Vec3::new(0.0, 8.0, 4.0), 1.5,
+                Material::diffuse_light(Texture::solid(Vec3::new(8.0, 8.0, 8.0))),
+            ),
+        ])
+    )
+}
+
+pub fn principled(aspect: Float) -> Scene {
+    let from    = Vec3::new(0.0, 3.0, 14.0);
+    let at      = Vec3::new(0.0, 1.0, 0.0);
+    let vup     = Vec3::new(0.0, 1.0, 0.0);
+    let vfov    = 30.0;
+    let red     = Texture::solid(Vec3::new(0.8, 0.1, 0.1));
+    let mut spheres = vec![
+        Geo::sphere(
+            Vec3::new(0.0, -1000.0, 0.0), 1000.0,
+            Material::lambertian(Texture::solid(Vec3::new(0.5, 0.5, 0.5))),
+        ),
+        Geo::sphere(
+            Vec3::new(0.0, 10.0, 6.0), 2.0,
+            Material::diffuse_light(Texture::solid(Vec3::new(6.0, 6.0, 6.0))),
+        ),
+    ];
+    let materials = vec![
+        Principled::gltf(Vec3::new(0.8, 0.1, 0.1), None, 0.0, 0.6, None),
+        Principled::gltf(Vec3::new(0.9, 0.6, 0.3), None, 1.0, 0.3, None),
+        Principled {
+            anisotropic: Param::constant(0.8),
+            ..Principled::gltf(Vec3::new(0.9, 0.9, 0.9), None, 1.0, 0.4, None)
+        },
+        Principled {
+            clearcoat: Param::constant(1.0),
+            ..Principled::new(red.clone())
+        },
+        Principled {
+            sheen: Param::constant(1.0),
+            roughness: Param::constant(1.0),
+            ..Principled::new(red)
+        },
+        Principled {
+            transmission: Param::constant(1.0),
+            roughness: Param::constant(0.1),
+            ..Principled::new(Texture::solid(Vec3::new(0.9, 1.0, 0.9)))
+        },
+    ];
+    let count = materials.len() as Float;
+    for (n, material) in materials.into_iter().enumerate() {
+        spheres.push(Geo::sphere(
+            Vec3::new(2.2 * (n as Float - (count - 1.0) / 2.0), 1.0, 0.0), 1.0,
+            Material::principled(material),
+        ));
+    }
+    Scene::new(
+        Camera::new(from, at, vup, vfov, aspect, None, None),
+        Geo::bvh_node(spheres),
+    )
+}
+
+pub fn coated(aspect: Float) -> Scene {
+    let from    = Vec3::new(0.0, 3.0, 12.0);
+    let at      = Vec3::new(0.0, 1.0, 0.0);
+    let vup     = Vec3::new(0.0, 1.0, 0.0);
+    let vfov    = 30.0;
+    Scene::new(
+        Camera::new(from, at, vup, vfov, aspect, None, None),
+        Geo::bvh_node(vec![
+            Geo::sphere(
+                Vec3::new(0.0, -1000.0, 0.0), 1000.0,
+                Material::lambertian(Texture::solid(Vec3::new(0.5, 0.5, 0.5))),
+            ),
+            // Car paint, a clear coat over a rough red metal
+            Geo::sphere(
+                Vec3::new(-2.2, 1.0, 0.0), 1.0,
+                Material::coated(
+                    Material::principled(
+                        Principled::gltf(Vec3::new(0.7, 0.05, 0.05), None, 0.6, 0.4, None),
+                    ),
+                    1.5, 0.0, Vec3::zeros(), 0.0,
+                ),
+            ),
+            // Varnish, an absorbing amber coat over a diffuse base
+            Geo::sphere(
+                Vec3::new(0.0, 1.0, 0.0), 1.0,
+                Material::coated(
+                    Material::lambertian(Texture::solid(Vec3::new(0.8, 0.6, 0.4))),
+                    1.5, 0.1, Vec3::new(0.1, 0.4, 1.2), 1.0,
+                ),
+            ),
+            // Lacquered copper
+            Geo::sphere(
+                Vec3::new(2.2, 1.0, 0.0), 1.0,
+                Material::coated(Material::copper(0.3), 1.5, 0.05, Vec3::zeros(), 0.0),
+            ),
+        ])
+    )
+}
+
+pub fn mix(aspect: Float) -> Scene {
+    let from    = Vec3::new(0.0, 3.0, 10.0);
+    let at      = Vec3::new(0.0, 1.0, 0.0);
+    let vup     = Vec3::new(0.0, 1.0, 0.0);
+    let vfov    = 30.0;
+    let rust    = Material::lambertian(Texture::solid(Vec3::new(0.4, 0.15, 0.05)));
+    let dirt    = Material::lambertian(Texture::solid(Vec3::new(0.3, 0.25, 0.2)));
+    Scene::new(
+        Camera::new(from, at, vup, vfov, aspect, None, None),
+        Geo::bvh_node(vec![
+            Geo::sphere(
+                Vec3::new(0.0, -1000.0, 0.0), 1000.0,
+                Material::lambertian(Texture::solid(Vec3::new(0.5, 0.5, 0.5))),
+            ),
+            // Rust patches on metal
+            Geo::sphere(
+                Vec3::new(-1.2, 1.0, 0.0), 1.0,
+                Material::mix(Material::aluminium(0.2), rust, Texture::noise()),
+            ),
+            // Dirt on paint
+            Geo::sphere(
+                Vec3::new(1.2, 1.0, 0.0), 1.0,
+                Material::mix(
+                    Material::coated(
+                        Material::lambertian(Texture::solid(Vec3::new(0.1, 0.2, 0.6))),
+                        1.5, 0.0, Vec3::zeros(), 0.0,
+                    ),
+                    dirt,
+                    Texture::checker(
+                        Texture::solid(Vec3::zeros()),
+                        Texture::solid(Vec3::ones()),
+                        8.0,
+                    ),
+                ),
+            ),
+        ])
+    )
+}
+
+pub fn tinted_glass(aspect: Float) -> Scene {
+    let from    = Vec3::new(0.0, 3.0, 10.0);
+    let at      = Vec3::new(0.0, 1.0, 0.0);
+    let vup     = Vec3::new(0.0, 1.0, 0.0);
+    let vfov    = 30.0;
+    // Glass that lets through this green after light travels one unit
+    let green   = Medium::from_color(Vec3::new(0.4, 0.8, 0.5), 1.0);
+    Scene::new(
+        Camera::new(from, at, vup, vfov, aspect, None, None),
+        Geo::bvh_node(vec![
+            Geo::sphere(
+                Vec3::new(0.0, -1000.0, 0.0), 1000.0,
+                Material::lambertian(Texture::checker(
+                    Texture::solid(Vec3::new(0.2, 0.2, 0.2)),
+                    Texture::solid(Vec3::new(0.9, 0.9, 0.9)),
+                    10.0,
+                )),
+            ),
+            Geo::sphere(Vec3::new(-2.0, 0.3, 0.5), 0.3, Material::glass(1.5, green)),
+            Geo::sphere(Vec3::new(0.0, 1.0, 0.0), 1.0, Material::glass(1.5, green)),
+            Geo::sphere(Vec3::new(2.4, 1.0, 0.0), 1.0, Material::rough_glass(1.5, 0.2, green)),
+        ])
+    )
+}
+
+pub fn dispersion(aspect: Float) -> Scene {
+    let from    = Vec3::new(0.0, 4.0, 10.0);
+    let at      = Vec3::new(0.0, 0.8, 0.0);
+    let vup     = Vec3::new(0.0, 1.0, 0.0);
+    let vfov    = 35.0;
+    let glass   = Material::dispersive(Texture::solid(Vec3::ones()), Ior::sf11(), None);
+    let mut objects = vec![
+        Geo::sphere(
+            Vec3::new(0.0, -1000.0, 0.0), 1000.0,
+            Material::lambertian(Texture::solid(Vec3::new(0.8, 0.8, 0.8))),
+        ),
+        Geo::sphere(
+            Vec3::new(2.5, 0.8, 1.0), 0.8,
+            Material::dispersive(Texture::solid(Vec3::ones()), Ior::diamond(), None),
+        ),
+        Geo::sphere(
+            Vec3::new(-2.5, 0.6, 1.0), 0.6,
+            Material::dispersive(Texture::solid(Vec3::ones()), Ior::bk7(), None),
+        ),
+        // SF10 flint glass, from the coefficients of Cauchy's equation
+        Geo::sphere(
+            Vec3::new(-1.2, 0.4, 2.2), 0.4,
+            Material::dispersive(Texture::solid(Vec3::ones()), Ior::cauchy(1.7280, 0.01342), None),
+        ),
+        // A narrow slit of light shining onto the prism
+        Geo::yz_rect(
+            0.8, 1.0, -0.2, 0.2, -6.0,
+            Material::diffuse_light(Texture::solid(Vec3::new(60.0, 60.0, 60.0))),
+        ),
+    ];
+    // A triangular prism along the z axis, with its faces wound outwards
+    let h = (3.0 as Float).sqrt();
+    let corners = [(-1.0, 0.0), (1.0, 0.0), (0.0, h)];
+    let (front, back) = (1.0, -1.0);
+    for n in 0..3 {
+        let (px, py) = corners[n];
+        let (qx, qy) = corners[(n + 1) % 3];
+        let (p0, p1) = (Vec3::new(px, py, front), Vec3::new(px, py, back));
+        let (q0, q1) = (Vec3::new(qx, qy, front), Vec3::new(qx, qy, back));
+        objects.push(Geo::triangle(p0, p1, q1, glass.clone()));
+        objects.push(Geo::triangle(p0, q1, q0, glass.clone()));
+    }
+    let corner = |n: usize, z: Float| Vec3::new(corners[n].0, corners[n].1, z);
+    objects.push(Geo::triangle(corner(0, front), corner(1, front), corner(2, front), glass.clone()));
+    objects.push(Geo::triangle(corner(0, back), corner(2, back), corner(1, back), glass));
+    Scene::new(
+        Camera::new(from, at, vup, vfov, aspect, None, None),
+        Geo::bvh_node(objects),
+    ).with_environment(Environment::black())
+}
+
+pub fn thin_film(aspect: Float) -> Scene {
+    let from    = Vec3::new(0.0, 3.0, 10.0);
+    let at      = Vec3::new(0.0, 1.0, 0.0);
+    let vup     = Vec3::new(0.0, 1.0, 0.0);
+    let vfov    = 30.0;
+    Scene::new(
+        Camera::new(from, at, vup, vfov, aspect, None, None),
+        Geo::bvh_node(vec![
+            Geo::sphere(
+                Vec3::new(0.0, -1000.0, 0.0), 1000.0,
+                Material::lambertian(Texture::solid(Vec3::new(0.5, 0.5, 0.5))),
+            ),
+            // A soap bubble, a film of water with air on both sides
+            Geo::sphere(
+                Vec3::new(-2.2, 1.2, 0.0), 1.0,
+                Material::dielectric(Texture::solid(Vec3::ones()), 1.0)
+                    .with_film(ThinFilm::new(380.0, 1.33)),
+            ),
+            // Anodized aluminium, an oxide layer over a rough metal
+            Geo::sphere(
+                Vec3::new(0.0, 1.0, 0.0), 1.0,
+                Material::aluminium(0.2).with_film(ThinFilm::new(300.0, 1.65)),
+            ),
+            // Heat tinted copper
+            Geo::sphere(
+                Vec3::new(2.2, 1.0, 0.0), 1.0,
+                Material::copper(0.1).with_film(ThinFilm::new(150.0, 2.6)),
+            ),
+        ])
+    )
+}
+
+pub fn subsurface(aspect: Float) -> Scene {
+    let from    = Vec3::new(0.0, 3.0, 10.0);
+    let at      = Vec3::new(0.0, 1.0, 0.0);
+    let vup     = Vec3::new(0.0, 1.0, 0.0);
+    let vfov    = 30.0;
+    Scene::new(
+        Camera::new(from, at, vup, vfov, aspect, None, None),
+        Geo::bvh_node(vec![
+            Geo::sphere(
+                Vec3::new(0.0, -1000.0, 0.0), 1000.0,
+                Material::lambertian(Texture::solid(Vec3::new(0.5, 0.5, 0.5))),
+            ),
+            // Skin, scattering red light furthest
+            Geo::sphere(
+                Vec3::new(-2.2, 1.0, 0.0), 1.0,
+                Material::subsurface(
+                    1.4, 0.3,
+                    Vec3::new(0.99, 0.90, 0.80),
+                    Vec3::new(0.40, 0.20, 0.12),
+                ),
+            ),
+            // Wax
+            Geo::sphere(
+                Vec3::new(0.0, 1.0, 0.0), 1.0,
+                Material::subsurface(
+                    1.45, 0.2,
+                    Vec3::new(0.99, 0.97, 0.90),
+                    Vec3::new(0.25, 0.25, 0.20),
+                ),
+            ),
+            // Marble
+            Geo::sphere(
+                Vec3::new(2.2, 1.0, 0.0), 1.0,
+                Material::subsurface(
+                    1.5, 0.05,
+                    Vec3::new(0.999, 0.998, 0.995),
+                    Vec3::new(0.15, 0.15, 0.18),
+                ),
+            ),
+            Geo::sphere(
+                Vec3::new(-4.0, 6.0, 4.0), 1.0,
+                Material::diffuse_light(Texture::solid(Vec3::new(10.0, 10.0, 10.0))),
+            ),
+        ])
+    ).with_environment(Environment::solid(Vec3::new(0.05, 0.05, 0.08)))
+}
+
+pub fn rough_diffuse(aspect: Float) -> Scene {
+    let from    = Vec3::new(0.0, 3.0, 10.0);
+    let at      = Vec3::new(0.0, 1.0, 0.0);
+    let vup     = Vec3::new(0.0, 1.0, 0.0);
+    let vfov    = 30.0;
+    let clay = Texture::solid(Vec3::new(0.75, 0.45, 0.3));
+    Scene::new(
+        Camera::new(from, at, vup, vfov, aspect, None, None),
+        Geo::bvh_node(vec![
+            Geo::sphere(
+                Vec3::new(0.0, -1000.0, 0.0), 1000.0,
+                Material::lambertian(Texture::solid(Vec3::new(0.5, 0.5, 0.5))),
+            ),
+            // Lambertian and increasingly rough clay
+            Geo::sphere(Vec3::new(-3.3, 1.0, 0.0), 1.0, Material::lambertian(clay.clone())),
+            Geo::sphere(Vec3::new(-1.1, 1.0, 0.0), 1.0, Material::oren_nayar(clay.clone(), 20.0)),
+            Geo::sphere(Vec3::new(1.1, 1.0, 0.0), 1.0, Material::oren_nayar(clay, 60.0)),
+            // A leaf, lit from behind
+            Geo::xy_rect(2.4, 4.2, 0.2, 2.0, 0.0, Material::diffuse_transmission(
+                Texture::solid(Vec3::new(0.10, 0.25, 0.05)),
+                Texture::solid(Vec3::new(0.25, 0.60, 0.10)),
+            )),
+            Geo::sphere(
+                Vec3::new(3.3, 1.6, -3.0), 0.5,
+                Material::diffuse_light(Texture::solid(Vec3::new(20.0, 20.0, 20.0))),
+            ),
+            Geo::sphere(
+                Vec3::new(-6.0, 4.0, 6.0), 1.0,
+                Material::diffuse_light(Texture::solid(Vec3::new(8.0, 8.0, 8.0))),
+            ),
+        ])
+    ).with_environment(Environment::solid(Vec3::new(0.05, 0.05, 0.08)))
+}
+
+pub fn bump_map(aspect: Float, normal_map: Option<&str>) -> Scene {
+    let from    = Vec3::new(0.0, 3.0, 10.0);
+    let at      = Vec3::new(0.0, 1.0, 0.0);
+    let vup     = Vec3::new(0.0, 1.0, 0.0);
+    let vfov    = 30.0;
+    // Tiles whose edges are pressed into the surface
+    let tiles = Texture::checker(
+        Texture::solid(Vec3::zeros()),
+        Texture::solid(Vec3::ones()),
+        2.0,
+    );
+    let plaster = Material::lambertian(Texture::solid(Vec3::new(0.8, 0.8, 0.75)));
+    let mapped = match normal_map {
+        Some(path) => plaster.with_normal_map(path),
+        None       => plaster,
+    };
+    Scene::new(
+        Camera::new(from, at, vup, vfov, aspect, None, None),
+        Geo::bvh_node(vec![
+            Geo::sphere(
+                Vec3::new(0.0, -1000.0, 0.0), 1000.0,
+                Material::lambertian(Texture::solid(Vec3::new(0.5, 0.5, 0.5)))
+                    .with_bump(tiles.clone(), 0.02),
+            ),
+            Geo::sphere(
+                Vec3::new(-2.2, 1.0, 0.0), 1.0,
+                Material::gold(0.2).with_bump(tiles, 0.01),
+            ),
+            Geo::sphere(Vec3::new(0.0, 1.0, 0.0), 1.0, mapped),
+            Geo::sphere(
+                Vec3::new(2.2, 1.0, 0.0), 1.0,
+                Material::lambertian(Texture::solid(Vec3::new(0.2, 0.4, 0.8)))
+                    .with_bump(Texture::noise(), 0.002),
+            ),
+            Geo::sphere(
+                Vec3::new(-4.0, 6.0, 4.0), 1.0,
+                Material::diffuse_light(Texture::solid(Vec3::new(10.0, 10.0, 10.0))),
+            ),
+        ])
+    ).with_environment(Environment::solid(Vec3::new(0.05, 0.05, 0.08)))
+}
+
+pub fn uv_checker(aspect: Float) -> Scene {
+    let from    = Vec3::new(0.0, 3.0, 10.0);
+    let at      = Vec3::new(0.0, 1.0, 0.0);
+    let vup     = Vec3::new(0.0, 1.0, 0.0);
+    let vfov    = 30.0;
+    let checker = |frequency: Float| Material::lambertian(Texture::uv_checker(
+        Texture::solid(Vec3::new(0.1, 0.1, 0.1)),
+        Texture::solid(Vec3::new(0.9, 0.9, 0.9)),
+        frequency,
+    ));
+    Scene::new(
+        Camera::new(from, at, vup, vfov, aspect, None, None),
+        Geo::bvh_node(vec![
+            Geo::xz_rect(-10.0, 10.0, -10.0, 10.0, 0.0, checker(20.0)),
+            Geo::sphere(Vec3::new(-2.2, 1.0, 0.0), 1.0, checker(16.0)),
+            Geo::xy_rect(-0.9, 0.9, 0.1, 1.9, 0.0, checker(8.0)),
+            Geo::triangle(
+                Vec3::new(1.3, 0.1, 0.0),
+                Vec3::new(3.1, 0.1, 0.0),
+                Vec3::new(1.3, 1.9, 0.0),
+                checker(8.0),
+            ),
+        ])
+    )
+}
+
+pub fn image_texture(aspect: Float, path: &str) -> Scene {
+    let from    = Vec3::new(0.0, 3.0, 10.0);
+    let at      = Vec3::new(0.0, 1.0, 0.0);
+    let vup     = Vec3::new(0.0, 1.0, 0.0);
+    let vfov    = 30.0;
+    let image = Texture::image(path);
+    Scene::new(
+        Camera::new(from, at, vup, vfov, aspect, None, None),
+        Geo::bvh_node(vec![
+            Geo::xz_rect(
+                -10.0, 10.0, -10.0, 10.0, 0.0,
+                Material::lambertian(image.clone().with_tiling(10.0)),
+            ),
+            Geo::sphere(
+                Vec3::new(-2.2, 1.0, 0.0), 1.0,
+                Material::lambertian(image.clone()),
+            ),
+            // The same image wrapped three ways
+            Geo::xy_rect(-1.0, 1.0, 0.0, 0.6, 0.0, Material::lambertian(
+                image.clone().with_tiling(3.0).with_wrap(Wrap::Repeat),
+            )),
+            Geo::xy_rect(-1.0, 1.0, 0.7, 1.3, 0.0, Material::lambertian(
+                image.clone().with_tiling(3.0).with_wrap(Wrap::Mirror),
+            )),
+            Geo::xy_rect(-1.0, 1.0, 1.4, 2.0, 0.0, Material::lambertian(
+                image.clone().with_tiling(3.0).with_wrap(Wrap::Clamp),
+            )),
+            // The image unfiltered and filtered without MIP levels
+            Geo::sphere(
+                Vec3::new(2.2, 1.0, 0.0), 1.0,
+                Material::lambertian(image.clone().with_filter(Filter::Nearest)),
+            ),
+            Geo::sphere(
+                Vec3::new(0.0, 0.4, 3.0), 0.4,
+                Material::lambertian(image.with_filter(Filter::Bilinear)),
+            ),
+            // A mirror, whose reflection of the floor is filtered too
+            Geo::sphere(
+                Vec3::new(0.0, 2.0, -5.0), 2.0,
+                Material::metal(Texture::solid(Vec3::new(0.9, 0.9, 0.9)), 0.0),
+            ),
+        ])
+    )
+}
+
+pub fn marble(aspect: Float) -> Scene {
+    let from    = Vec3::new(13.0, 2.0, 3.0);
+    let at      = Vec3::new(0.0, 1.0, 0.0);
+    let vup     = Vec3::new(0.0, 1.0, 0.0);
+    let vfov    = 20.0;
+    Scene::new(
+        Camera::new(from, at, vup, vfov, aspect, None, None),
+        Geo::bvh_node(vec![
+            Geo::sphere(
+                Vec3::new(0.0, -1000.0, 0.0), 1000.0,
+                Material::lambertian(Texture::turbulence(4.0, 1)),
+            ),
+            Geo::sphere(
+                Vec3::new(0.0, 2.0, -1.2), 2.0,
+                Material::lambertian(Texture::marble(4.0, 2)),
+            ),
+            Geo::sphere(
+                Vec3::new(0.0, 1.0, 2.0), 1.0,
+                Material::lambertian(Texture::scale(
+                    Texture::noise(),
+                    Vec3::new(0.9, 0.6, 0.3),
+                )),
+            ),
+        ])
+    )
+}
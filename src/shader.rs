@@ -1,51 +1,205 @@
+use util::{rand, Float};
 use vec3::Vec3;
 use scene::Scene;
 use ray::Ray;
-use std;
+use geo::Intersection;
+use light::LightSample;
+use material::Lobe;
+use medium::Medium;
+use spectrum::{sample_wavelength, sample_wavelengths, spectrum_to_rgb};
 
-const EPSILON: f64 = 0.0001;
 const MAX_DEPTH: u32 = 50;
+const MAX_SCATTERS: u32 = 1024;
 
 /// Returns a color vector for a given subpixel into a scene
 ///
 /// # Arguments
-/// * `scene`  - the scene being rendered
-/// * `x`      - the subpixel x position
-/// * `y`      - the subpixel y position
-/// * `width`  - the screen's width in pixels
-/// * `height` - the screen's height in pixels
+/// * `scene`    - the scene being rendered
+/// * `x`        - the subpixel x position
+/// * `y`        - the subpixel y position
+/// * `width`    - the screen's width in pixels
+/// * `height`   - the screen's height in pixels
+/// * `spectral` - whether to trace light spectrally rather than in RGB
 ///
-pub fn shader(scene: &Scene, x: f64, y: f64, width: f64, height: f64) -> Vec3 {
+pub fn shader(
+    scene: &Scene,
+    x: Float,
+    y: Float,
+    width: Float,
+    height: Float,
+    spectral: bool,
+) -> Vec3 {
     let u = x / width;
     let v = y / height;
-    let ray = scene.camera.ray(u, v);
-    shade(scene, ray, 0)
+    let ray = scene.camera.ray_differential(u, v, 1.0 / width, 1.0 / height);
+    if spectral {
+        let wavelengths = sample_wavelengths(rand());
+        spectrum_to_rgb(shade(scene, ray, 0, None, None, Some(wavelengths)), wavelengths)
+    } else {
+        shade(scene, ray, 0, None, None, None)
+    }
 }
 
 /// The shader implementation of this ray tracer. Takes a scene and a ray and
-/// and determiens the resulting color.
+/// and determiens the resulting color. Light is gathered both by sampling
+/// lights directly and by following the scattered ray, with the two combined
+/// through multiple importance sampling. When tracing spectrally the result
+/// is the radiance at each of the path's wavelengths rather than a color.
 ///
 /// # Arguments
-/// * `scene` - the scene being rendered
-/// * `ray`   - the incoming ray
-/// * `depth` - the number of recursive calls, cuts off at MAX_DEPTH
+/// * `scene`       - the scene being rendered
+/// * `ray`         - the incoming ray
+/// * `depth`       - the number of recursive calls, cuts off at MAX_DEPTH
+/// * `pdf`         - the density the ray was scattered with, None for camera
+///                   rays and specular bounces which lights can not be
+///                   sampled for
+/// * `medium`      - the medium the ray travels through, None outside any
+///                   object
+/// * `wavelengths` - the wavelengths traced by a spectral path, None when
+///                   tracing in RGB
 ///
-fn shade(scene: &Scene, ray: Ray, depth: u32) -> Vec3 {
-    match scene.intersects(ray, EPSILON, std::f64::MAX) {
-        Some((attenuation, scattered)) => {
-            if depth < MAX_DEPTH {
-                if let Some(scatter_ray) = scattered {
-                    return attenuation * shade(scene, scatter_ray, depth + 1);
+fn shade(
+    scene: &Scene,
+    ray: Ray,
+    depth: u32,
+    pdf: Option<Float>,
+    medium: Option<Medium>,
+    wavelengths: Option<Vec3>,
+) -> Vec3 {
+    // A ray through a scattering medium takes a random walk until it reaches
+    // a surface, while other media only absorb light along the way
+    let (mut ray, mut pdf, mut transmittance) = (ray, pdf, Vec3::ones());
+    let mut scatters = 0;
+    let hit = loop {
+        let hit = scene.intersects(ray, 0.0, Float::MAX);
+        let dist = hit.as_ref().map_or(Float::INFINITY, |i| i.t);
+        match medium {
+            Some(medium) if medium.is_scattering() => {
+                let (scattered, sample_weight) = medium.sample(dist, wavelengths);
+                transmittance = transmittance * sample_weight;
+                if let Some(t) = scattered {
+                    scatters += 1;
+                    if scatters > MAX_SCATTERS {
+                        return Vec3::zeros();
+                    }
+                    let dir = medium.sample_direction(ray.dir);
+                    ray = Ray { wavelength: ray.wavelength, ..Ray::new(ray.at(t), dir, ray.time) };
+                    pdf = None;
+                    continue;
                 }
-            }
-            Vec3::zeros()
+            },
+            Some(medium) if hit.is_some() => {
+                transmittance = medium.transmittance(dist, wavelengths);
+            },
+            _ => (),
         }
-        None => background(ray),
+        break hit;
+    };
+    // Emission found by following a scattered ray is weighted against the
+    // chance of having found it through light sampling instead
+    let weight = match pdf {
+        Some(pdf) => power_heuristic(pdf, scene.light_pdf(ray)),
+        None      => 1.0,
+    };
+    let i = match hit {
+        Some(i) => i.with_differentials(),
+        None    => return transmittance * scene.environment.value(ray, wavelengths) * weight,
+    };
+    let emitted = i.material.emitted(&i, wavelengths) * weight;
+    if depth >= MAX_DEPTH {
+        return transmittance * emitted;
+    }
+    // A dispersive surface splits the path into a single wavelength, which
+    // the path carries from then on. Spectral paths keep their hero
+    // wavelength and drop the others.
+    let (i, spectral) = if i.material.is_dispersive() && i.ray.wavelength.is_none() {
+        let (wavelength, spectral) = match wavelengths {
+            Some(wavelengths) => (wavelengths.x, Vec3::new(3.0, 0.0, 0.0)),
+            None              => sample_wavelength(rand()),
+        };
+        (Intersection { ray: Ray { wavelength: Some(wavelength), ..i.ray }, ..i }, spectral)
+    } else {
+        (i, Vec3::ones())
+    };
+    let sample = match i.material.sample(&i, wavelengths) {
+        Some(sample) => sample,
+        None         => return transmittance * emitted,
+    };
+    // Specular bounces keep the ray's differentials, so textures seen in
+    // mirrors and through glass are filtered by their footprint
+    let scatter_ray = match sample.lobe {
+        Lobe::Specular => Ray::specular(&i, sample.dir, i.material.ref_idx(&i)),
+        _              => Ray::from_intersection(&i, sample.dir),
+    };
+    let direct = if i.material.is_specular() {
+        Vec3::zeros()
+    } else {
+        direct(scene, &i, wavelengths)
+    };
+    // Lights can not be sampled towards a specular lobe, so light found
+    // through one is unweighted
+    let pdf = match sample.lobe {
+        Lobe::Specular => None,
+        _              => Some(sample.pdf),
+    };
+    // Transmission through a surface filled with a medium enters it from
+    // outside or leaves it from inside, media are not nested
+    let transmitted = Vec3::dot(sample.dir, i.normal) * Vec3::dot(i.ray.dir, i.normal) > 0.0;
+    let medium = match (transmitted, i.material.medium()) {
+        (true, Some(inside)) => if medium.is_some() { None } else { Some(inside) },
+        _                    => medium,
+    };
+    transmittance * (
+        emitted + spectral * (
+            direct + sample.weight
+                * shade(scene, scatter_ray, depth + 1, pdf, medium, wavelengths)
+        )
+    )
+}
+
+/// Returns the light arriving at an intersection directly from a sampled
+/// light, weighted against the chance of having scattered towards it.
+///
+/// # Arguments
+/// * `scene`       - the scene being rendered
+/// * `i`           - the intersection being lit
+/// * `wavelengths` - the wavelengths traced by a spectral path, None when
+///                   tracing in RGB
+///
+fn direct(scene: &Scene, i: &Intersection, wavelengths: Option<Vec3>) -> Vec3 {
+    match scene.sample_light(i.point, i.ray.time, wavelengths) {
+        // Area and environment lights may also be found by scattering, so
+        // are weighted
+        Some((LightSample::Traced { dir }, _)) => {
+            let ray = Ray::from_intersection(i, dir);
+            let light_pdf = scene.light_pdf(ray);
+            if light_pdf <= 0.0 {
+                return Vec3::zeros();
+            }
+            let radiance = match scene.intersects(ray, 0.0, Float::MAX) {
+                Some(l) => l.material.emitted(&l, wavelengths),
+                None    => scene.environment.value(ray, wavelengths),
+            };
+            radiance
+                * i.material.eval(i, ray.dir, wavelengths)
+                * power_heuristic(light_pdf, i.material.pdf(i, ray.dir))
+                / light_pdf
+        },
+        // Delta lights can only be found by sampling, so are unweighted
+        Some((LightSample::Delta { dir, dist, radiance }, chance)) => {
+            let ray = Ray::from_intersection(i, dir);
+            match scene.intersects(ray, 0.0, dist) {
+                Some(_) => Vec3::zeros(),
+                None    => radiance * i.material.eval(i, ray.dir, wavelengths) / chance,
+            }
+        },
+        None => Vec3::zeros(),
     }
 }
 
-/// Returns a background color given a ray
-fn background(ray: Ray) -> Vec3 {
-    let t = (ray.dir.y + 1.0) * 0.5;
-    (1.0 - t) * Vec3::ones() + t * Vec3::new(0.5, 0.7, 1.0)
+/// The power heuristic for multiple importance sampling, returns the weight
+/// of a sample taken with density `f` against a strategy of density `g`
+fn power_heuristic(f: Float, g: Float) -> Float {
+    let (f2, g2) = (f * f, g * g);
+    if f2 + g2 > 0.0 { f2 / (f2 + g2) } else { 0.0 }
 }
@@ -0,0 +1,162 @@
+use util::{rand, Float, PI};
+use vec3::Vec3;
+use color::xyz_to_rgb;
+
+/// The angular radius of the sun in degrees
+const SUN_RADIUS: Float = 0.265;
+/// Scales the sky's luminance, given in kcd/m^2, into scene units
+const SKY_SCALE: Float = 0.05;
+/// The radiance of the sun before it is attenuated by the atmosphere
+const SUN_RADIANCE: Float = 40000.0;
+
+#[derive(Debug, Clone)]
+pub struct Sky {
+    /// The direction towards the sun
+    sun: Vec3,
+    /// The cosine of the sun's angular radius
+    cos_sun: Float,
+    /// The radiance of the sun disk
+    sun_radiance: Vec3,
+    /// The zenith luminance and chromaticity as (Y, x, y)
+    zenith: Vec3,
+    /// The Perez coefficients A through E for each of Y, x and y
+    perez: [[Float; 5]; 3],
+    /// The Perez function of each of Y, x and y at the zenith
+    perez_zenith: Vec3,
+    /// The factor the sky and sun radiance is scaled by
+    scale: Float,
+}
+
+impl Sky {
+    /// Creates a new Preetham daylight sky
+    ///
+    /// # Arguments
+    /// * `elevation` - the sun's angle above the horizon in degrees
+    /// * `azimuth`   - the sun's angle from the x axis towards the z axis in
+    ///                 degrees
+    /// * `turbidity` - the haziness of the atmosphere, from 2 for a clear
+    ///                 sky to 10 for a hazy one
+    /// * `scale`     - the factor the sky and sun radiance is scaled by
+    ///
+    pub fn new(elevation: Float, azimuth: Float, turbidity: Float, scale: Float) -> Self {
+        let (el, az) = (elevation.to_radians(), azimuth.to_radians());
+        let sun = Vec3::new(el.cos() * az.cos(), el.sin(), el.cos() * az.sin());
+        let t  = turbidity;
+        let ts = (PI / 2.0 - el).max(0.0).min(PI / 2.0);
+        // Zenith luminance and chromaticity
+        let chi = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * ts);
+        let y_lum = (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192;
+        let (ts2, ts3) = (ts * ts, ts * ts * ts);
+        let x_chroma = t * t * (0.00166 * ts3 - 0.00375 * ts2 + 0.00209 * ts)
+            + t * (-0.02903 * ts3 + 0.06377 * ts2 - 0.03202 * ts + 0.00394)
+            + (0.11693 * ts3 - 0.21196 * ts2 + 0.06052 * ts + 0.25886);
+        let y_chroma = t * t * (0.00275 * ts3 - 0.00610 * ts2 + 0.00317 * ts)
+            + t * (-0.04214 * ts3 + 0.08970 * ts2 - 0.04153 * ts + 0.00516)
+            + (0.15346 * ts3 - 0.26756 * ts2 + 0.06670 * ts + 0.26688);
+        let perez = [
+            [
+                0.1787 * t - 1.4630, -0.3554 * t + 0.4275, -0.0227 * t + 5.3251,
+                0.1206 * t - 2.5771, -0.0670 * t + 0.3703,
+            ],
+            [
+                -0.0193 * t - 0.2592, -0.0665 * t + 0.0008, -0.0004 * t + 0.2125,
+                -0.0641 * t - 0.8989, -0.0033 * t + 0.0452,
+            ],
+            [
+                -0.0167 * t - 0.2608, -0.0950 * t + 0.0092, -0.0079 * t + 0.2102,
+                -0.0441 * t - 1.6537, -0.0109 * t + 0.0529,
+            ],
+        ];
+        let perez_zenith = Vec3::new(
+            perez_function(&perez[0], 0.0, ts),
+            perez_function(&perez[1], 0.0, ts),
+            perez_function(&perez[2], 0.0, ts),
+        );
+        Sky {
+            sun:          sun,
+            cos_sun:      SUN_RADIUS.to_radians().cos(),
+            sun_radiance: sun_radiance(ts, t),
+            zenith:       Vec3::new(y_lum, x_chroma, y_chroma),
+            perez:        perez,
+            perez_zenith: perez_zenith,
+            scale:        scale,
+        }
+    }
+
+    /// Returns the radiance arriving from the given direction
+    pub fn radiance(&self, dir: Vec3) -> Vec3 {
+        let dir = dir.unit();
+        let cos_gamma = Vec3::dot(dir, self.sun);
+        if cos_gamma >= self.cos_sun && self.sun.y > 0.0 {
+            return self.sun_radiance * self.scale;
+        }
+        // The model is undefined below the horizon, so continue the horizon
+        let theta = dir.y.max(0.001).acos();
+        let gamma = cos_gamma.max(-1.0).min(1.0).acos();
+        let y_lum = self.zenith.x
+            * perez_function(&self.perez[0], theta, gamma) / self.perez_zenith.x;
+        let x = self.zenith.y
+            * perez_function(&self.perez[1], theta, gamma) / self.perez_zenith.y;
+        let y = self.zenith.z
+            * perez_function(&self.perez[2], theta, gamma) / self.perez_zenith.z;
+        let xyz = Vec3::new(x / y * y_lum, y_lum, (1.0 - x - y) / y * y_lum);
+        let rgb = xyz_to_rgb(xyz);
+        Vec3::new(rgb.x.max(0.0), rgb.y.max(0.0), rgb.z.max(0.0)) * SKY_SCALE * self.scale
+    }
+
+    /// Samples a direction towards the sun disk
+    pub fn sample(&self) -> Vec3 {
+        let z   = 1.0 + rand() * (self.cos_sun - 1.0);
+        let phi = 2.0 * PI * rand();
+        let sin = (1.0 - z * z).max(0.0).sqrt();
+        let (u, v) = Vec3::basis(self.sun);
+        sin * phi.cos() * u + sin * phi.sin() * v + z * self.sun
+    }
+
+    /// Returns the solid angle density with which `sample` produces a
+    /// direction
+    pub fn pdf(&self, dir: Vec3) -> Float {
+        if Vec3::dot(dir.unit(), self.sun) >= self.cos_sun {
+            1.0 / (2.0 * PI * (1.0 - self.cos_sun))
+        } else {
+            0.0
+        }
+    }
+}
+
+/// The Perez sky model distribution function
+///
+/// # Arguments
+/// * `c`     - the coefficients A through E
+/// * `theta` - the angle of the view direction from the zenith
+/// * `gamma` - the angle of the view direction from the sun
+///
+fn perez_function(c: &[Float; 5], theta: Float, gamma: Float) -> Float {
+    (1.0 + c[0] * (c[1] / theta.cos()).exp())
+        * (1.0 + c[2] * (c[3] * gamma).exp() + c[4] * gamma.cos() * gamma.cos())
+}
+
+/// Returns the radiance of the sun disk after passing through the atmosphere,
+/// from Rayleigh and aerosol transmittance at red, green and blue wavelengths
+///
+/// # Arguments
+/// * `ts`        - the angle of the sun from the zenith
+/// * `turbidity` - the haziness of the atmosphere
+///
+fn sun_radiance(ts: Float, turbidity: Float) -> Vec3 {
+    // Relative optical mass of the atmosphere
+    let m = 1.0 / (ts.cos() + 0.15 * (93.885 - ts.to_degrees()).powf(-1.253));
+    let beta  = 0.04608 * turbidity - 0.04586;
+    let alpha = 1.3;
+    let transmittance = |lambda: Float| {
+        let rayleigh = (-0.008735 * lambda.powf(-4.08) * m).exp();
+        let aerosol  = (-beta * lambda.powf(-alpha) * m).exp();
+        rayleigh * aerosol
+    };
+    // Wavelengths in micrometers
+    Vec3::new(
+        transmittance(0.680),
+        transmittance(0.550),
+        transmittance(0.440),
+    ) * SUN_RADIANCE
+}
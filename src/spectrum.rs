@@ -0,0 +1,130 @@
+use util::Float;
+use vec3::Vec3;
+use color::xyz_to_rgb;
+
+/// The shortest visible wavelength in nanometers
+pub const WAVELENGTH_MIN: Float = 380.0;
+/// The longest visible wavelength in nanometers
+pub const WAVELENGTH_MAX: Float = 780.0;
+
+// Smits' basis spectra for upsampling RGB reflectances, in ten bins evenly
+// spanning 380nm to 720nm
+const WHITE:   [Float; 10] = [1.0000, 1.0000, 0.9999, 0.9993, 0.9992, 0.9998, 1.0000, 1.0000, 1.0000, 1.0000];
+const CYAN:    [Float; 10] = [0.9710, 0.9426, 1.0007, 1.0007, 1.0007, 1.0007, 0.1564, 0.0000, 0.0000, 0.0000];
+const MAGENTA: [Float; 10] = [1.0000, 1.0000, 0.9685, 0.2229, 0.0000, 0.0458, 0.8369, 1.0000, 1.0000, 0.9959];
+const YELLOW:  [Float; 10] = [0.0001, 0.0000, 0.1088, 0.6651, 1.0000, 1.0000, 0.9996, 0.9586, 0.9685, 0.9840];
+const RED:     [Float; 10] = [0.1012, 0.0515, 0.0000, 0.0000, 0.0000, 0.0000, 0.8325, 1.0149, 1.0149, 1.0149];
+const GREEN:   [Float; 10] = [0.0000, 0.0000, 0.0273, 0.7937, 1.0000, 0.9418, 0.1719, 0.0000, 0.0000, 0.0025];
+const BLUE:    [Float; 10] = [1.0000, 1.0000, 0.8916, 0.3323, 0.0000, 0.0000, 0.0003, 0.0369, 0.0483, 0.0496];
+
+/// Returns the CIE 1931 color matching functions at a wavelength in
+/// nanometers, using the multi-lobe fit of Wyman, Sloan and Shirley
+pub fn cie_xyz(wavelength: Float) -> Vec3 {
+    let g = |mu: Float, s1: Float, s2: Float| {
+        let s = if wavelength < mu { s1 } else { s2 };
+        (-0.5 * (wavelength - mu) * (wavelength - mu) / (s * s)).exp()
+    };
+    Vec3::new(
+        1.056 * g(599.8, 37.9, 31.0) + 0.362 * g(442.0, 16.0, 26.7) - 0.065 * g(501.1, 20.4, 26.2),
+        0.821 * g(568.8, 46.9, 40.5) + 0.286 * g(530.9, 16.3, 31.1),
+        1.217 * g(437.0, 11.8, 36.0) + 0.681 * g(459.0, 26.0, 13.8),
+    )
+}
+
+/// Returns the RGB weight of light at a wavelength in nanometers. The
+/// weights average to white over all visible wavelengths, so averaging them
+/// over uniformly sampled wavelengths integrates a spectrum to RGB.
+pub fn rgb_weight(wavelength: Float) -> Vec3 {
+    // The average of the RGB color of each visible wavelength
+    let average = Vec3::new(0.320907, 0.253872, 0.242624);
+    xyz_to_rgb(cie_xyz(wavelength)) / average
+}
+
+/// Uniformly samples a visible wavelength, returning it along with its RGB
+/// weight, so a path carrying a single wavelength stays unbiased
+///
+/// # Arguments
+/// * `u` - a uniform random number in [0, 1)
+///
+pub fn sample_wavelength(u: Float) -> (Float, Vec3) {
+    let wavelength = WAVELENGTH_MIN + u * (WAVELENGTH_MAX - WAVELENGTH_MIN);
+    (wavelength, rgb_weight(wavelength))
+}
+
+/// Samples a hero wavelength along with two more evenly rotated through the
+/// visible range, each of which is uniformly distributed
+///
+/// # Arguments
+/// * `u` - a uniform random number in [0, 1)
+///
+pub fn sample_wavelengths(u: Float) -> Vec3 {
+    let range = WAVELENGTH_MAX - WAVELENGTH_MIN;
+    let rotate = |k: Float| WAVELENGTH_MIN + ((u + k / 3.0) % 1.0) * range;
+    Vec3::new(rotate(0.0), rotate(1.0), rotate(2.0))
+}
+
+/// Returns the value at a wavelength in nanometers of a smooth spectrum
+/// whose color is the given RGB color, using Smits' method
+pub fn rgb_to_spectrum(rgb: Vec3, wavelength: Float) -> Float {
+    let bin = ((wavelength - WAVELENGTH_MIN) / 34.0).max(0.0).min(9.0) as usize;
+    let (r, g, b) = (rgb.x.max(0.0), rgb.y.max(0.0), rgb.z.max(0.0));
+    if r <= g && r <= b {
+        r * WHITE[bin] + if g <= b {
+            (g - r) * CYAN[bin] + (b - g) * BLUE[bin]
+        } else {
+            (b - r) * CYAN[bin] + (g - b) * GREEN[bin]
+        }
+    } else if g <= r && g <= b {
+        g * WHITE[bin] + if r <= b {
+            (r - g) * MAGENTA[bin] + (b - r) * BLUE[bin]
+        } else {
+            (b - g) * MAGENTA[bin] + (r - b) * RED[bin]
+        }
+    } else {
+        b * WHITE[bin] + if r <= g {
+            (r - b) * YELLOW[bin] + (g - r) * GREEN[bin]
+        } else {
+            (g - b) * YELLOW[bin] + (r - g) * RED[bin]
+        }
+    }
+}
+
+/// Returns the values of the spectrum of an RGB color at three wavelengths
+pub fn upsample(rgb: Vec3, wavelengths: Vec3) -> Vec3 {
+    Vec3::new(
+        rgb_to_spectrum(rgb, wavelengths.x),
+        rgb_to_spectrum(rgb, wavelengths.y),
+        rgb_to_spectrum(rgb, wavelengths.z),
+    )
+}
+
+/// Returns the values at a path's wavelengths of the spectrum of an RGB
+/// reflectance, or the color itself for paths traced in RGB. Smits' method
+/// is linear in the color, so absorption and scattering coefficients are
+/// upsampled the same way.
+pub fn reflectance(rgb: Vec3, wavelengths: Option<Vec3>) -> Vec3 {
+    match wavelengths {
+        Some(wavelengths) => upsample(rgb, wavelengths),
+        None              => rgb,
+    }
+}
+
+/// Returns the values at a path's wavelengths of the spectrum of an RGB
+/// radiance, or the color itself for paths traced in RGB.
+///
+/// Emitters share the reflectance basis. An illuminant basis, as in PBRT,
+/// makes white the spectrum of the RGB space's white point rather than a
+/// constant, but `spectrum_to_rgb` normalizes the color matching functions
+/// so a constant spectrum is white, which an illuminant basis would tint.
+/// Being linear, the basis holds for radiance above 1 too.
+pub fn illuminant(rgb: Vec3, wavelengths: Option<Vec3>) -> Vec3 {
+    reflectance(rgb, wavelengths)
+}
+
+/// Integrates the values of a spectrum at three uniformly sampled
+/// wavelengths through the CIE color matching functions to an RGB color
+pub fn spectrum_to_rgb(values: Vec3, wavelengths: Vec3) -> Vec3 {
+    (values.x * rgb_weight(wavelengths.x)
+        + values.y * rgb_weight(wavelengths.y)
+        + values.z * rgb_weight(wavelengths.z)) / 3.0
+}
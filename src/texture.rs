@@ -1,6 +1,13 @@
+use util::Float;
 use vec3::Vec3;
 use geo::Intersection;
 use noise::Perlin;
+use image_map::{Filter, ImageMap, Wrap};
+use std::path::Path;
+use std::sync::Arc;
+
+/// The number of octaves summed for turbulence
+const TURBULENCE_OCTAVES: u32 = 7;
 
 #[derive(Debug, Clone)]
 pub enum Texture {
@@ -12,12 +19,43 @@ pub enum Texture {
     Checker {
         odd: Box<Texture>,
         even: Box<Texture>,
-        frequency: f64,
+        frequency: Float,
+    },
+    /// Texture representation of a checker pattern across the surface
+    /// coordinates
+    UvChecker {
+        odd: Box<Texture>,
+        even: Box<Texture>,
+        frequency: Float,
     },
     /// Noise texture
     Noise {
         perlin: Box<Perlin>,
     },
+    /// Fractal turbulence texture, with the frequency of its first octave
+    Turbulence {
+        perlin: Box<Perlin>,
+        scale: Float,
+    },
+    /// Marble texture of sine bands along z distorted by turbulence, with
+    /// the frequency of the bands
+    Marble {
+        perlin: Box<Perlin>,
+        scale: Float,
+    },
+    /// Texture scaled by a constant color
+    Scale {
+        texture: Box<Texture>,
+        scale: Vec3,
+    },
+    /// Texture of an image mapped across the surface coordinates, tiled a
+    /// number of times along each
+    Image {
+        image: Arc<ImageMap>,
+        filter: Filter,
+        wrap: Wrap,
+        tiling: Float,
+    },
 }
 
 impl Texture {
@@ -27,7 +65,7 @@ impl Texture {
     }
 
     /// Creates a new checker texture
-    pub fn checker(odd: Texture, even: Texture, frequency: f64) -> Self {
+    pub fn checker(odd: Texture, even: Texture, frequency: Float) -> Self {
         Texture::Checker {
             odd:  Box::new(odd),
             even: Box::new(even),
@@ -35,11 +73,98 @@ impl Texture {
         }
     }
 
+    /// Creates a new checker texture with `frequency` squares along each
+    /// surface coordinate
+    pub fn uv_checker(odd: Texture, even: Texture, frequency: Float) -> Self {
+        Texture::UvChecker {
+            odd:  Box::new(odd),
+            even: Box::new(even),
+            frequency,
+        }
+    }
+
     /// Creates a mew noise texture
     pub fn noise() -> Self {
         Texture::Noise { perlin: Box::new(Perlin::new()) }
     }
 
+    /// Creates a new turbulence texture, which is the same for the same seed
+    pub fn turbulence(scale: Float, seed: u64) -> Self {
+        Texture::Turbulence { perlin: Box::new(Perlin::seeded(seed)), scale }
+    }
+
+    /// Creates a new marble texture, which is the same for the same seed
+    pub fn marble(scale: Float, seed: u64) -> Self {
+        Texture::Marble { perlin: Box::new(Perlin::seeded(seed)), scale }
+    }
+
+    /// Creates a new texture scaled by a constant color
+    pub fn scale(texture: Texture, scale: Vec3) -> Self {
+        Texture::Scale {
+            texture: Box::new(texture),
+            scale,
+        }
+    }
+
+    /// Creates a new image texture from an sRGB encoded .png or .jpg,
+    /// trilinearly filtered and repeating across the surface
+    pub fn image<P: AsRef<Path>>(path: P) -> Self {
+        Texture::Image {
+            image:  Arc::new(ImageMap::load_srgb(path)),
+            filter: Filter::Trilinear,
+            wrap:   Wrap::Repeat,
+            tiling: 1.0,
+        }
+    }
+
+    /// Sets how an image texture is filtered
+    pub fn with_filter(self, filter: Filter) -> Self {
+        match self {
+            Texture::Image { image, wrap, tiling, .. } => {
+                Texture::Image { image, filter, wrap, tiling }
+            },
+            other => panic!("{:?} can not be filtered!", other),
+        }
+    }
+
+    /// Sets how an image texture extends beyond the [0, 1] surface
+    /// coordinates
+    pub fn with_wrap(self, wrap: Wrap) -> Self {
+        match self {
+            Texture::Image { image, filter, tiling, .. } => {
+                Texture::Image { image, filter, wrap, tiling }
+            },
+            other => panic!("{:?} can not be wrapped!", other),
+        }
+    }
+
+    /// Sets the number of times an image texture spans the [0, 1] surface
+    /// coordinates
+    pub fn with_tiling(self, tiling: Float) -> Self {
+        match self {
+            Texture::Image { image, filter, wrap, .. } => {
+                Texture::Image { image, filter, wrap, tiling }
+            },
+            other => panic!("{:?} can not be tiled!", other),
+        }
+    }
+
+    /// Gets the approximate average value across the texture
+    pub fn average(&self) -> Vec3 {
+        match self {
+            &Texture::Solid { color } => color,
+            &Texture::Checker { ref odd, ref even, .. } |
+            &Texture::UvChecker { ref odd, ref even, .. } => {
+                (odd.average() + even.average()) / 2.0
+            },
+            &Texture::Noise { .. } |
+            &Texture::Turbulence { .. } |
+            &Texture::Marble { .. } => Vec3::ones() * 0.5,
+            &Texture::Scale { ref texture, scale } => texture.average() * scale,
+            &Texture::Image { ref image, .. } => image.average(),
+        }
+    }
+
     /// Gets the value at a given coordinate
     pub fn value(&self, i: &Intersection) -> Vec3 {
         match self {
@@ -52,8 +177,31 @@ impl Texture {
                           * (frequency * i.point.z).sin();
                 if sines < 0.0 { odd.value(i) } else { even.value(i) }
             }
+            // Handle texture value for surface checker
+            &Texture::UvChecker { ref odd, ref even, frequency } => {
+                let square = (frequency * i.u).floor() + (frequency * i.v).floor();
+                if square % 2.0 == 0.0 { even.value(i) } else { odd.value(i) }
+            },
             // Handle noise texture
-            &Texture::Noise { ref perlin } => Vec3::ones() * perlin.noise(i.point),
+            &Texture::Noise { ref perlin } => {
+                Vec3::ones() * 0.5 * (1.0 + perlin.noise(4.0 * i.point))
+            },
+            // Handle turbulence texture
+            &Texture::Turbulence { ref perlin, scale } => {
+                Vec3::ones() * perlin.turbulence(scale * i.point, TURBULENCE_OCTAVES)
+            },
+            // Handle marble texture
+            &Texture::Marble { ref perlin, scale } => {
+                let turbulence = perlin.turbulence(i.point, TURBULENCE_OCTAVES);
+                Vec3::ones() * 0.5 * (1.0 + (scale * i.point.z + 10.0 * turbulence).sin())
+            },
+            // Handle scaled texture
+            &Texture::Scale { ref texture, scale } => texture.value(i) * scale,
+            // Handle image texture
+            &Texture::Image { ref image, filter, wrap, tiling } => {
+                let width = i.uv_width() * tiling;
+                image.lookup(i.u * tiling, i.v * tiling, width, filter, wrap)
+            },
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,203 @@
+use util::{Float, PI};
+use vec3::Vec3;
+use spectrum::{rgb_weight, WAVELENGTH_MIN, WAVELENGTH_MAX};
+use std::ops::{Add, Div, Mul, Sub};
+
+/// The number of wavelengths film reflectance is integrated over for RGB
+const RGB_WAVELENGTHS: usize = 16;
+
+#[derive(Debug, Clone, Copy)]
+pub struct ThinFilm {
+    /// The thickness of the film in nanometers
+    pub thickness: Float,
+    /// The index of refraction of the film
+    pub ior: Float,
+}
+
+impl ThinFilm {
+    /// Creates a new thin film
+    ///
+    /// # Arguments
+    /// * `thickness` - the thickness of the film in nanometers
+    /// * `ior`       - the index of refraction of the film
+    ///
+    pub fn new(thickness: Float, ior: Float) -> Self {
+        ThinFilm { thickness, ior }
+    }
+
+    /// Returns the reflectance of the film over a base at a single
+    /// wavelength, summing the light reflected back and forth inside the film
+    /// through Airy's formula
+    ///
+    /// # Arguments
+    /// * `cos_i`      - the cosine of the incident angle
+    /// * `n`          - the index of refraction of the incident medium
+    /// * `eta`        - the real part of the base's index of refraction
+    /// * `k`          - the imaginary part of the base's index of refraction
+    /// * `wavelength` - the wavelength in nanometers
+    ///
+    pub fn reflectance(
+        &self,
+        cos_i: Float,
+        n: Float,
+        eta: Float,
+        k: Float,
+        wavelength: Float,
+    ) -> Float {
+        let cos_i = cos_i.abs().min(1.0);
+        let sin2_i = 1.0 - cos_i * cos_i;
+        // Refract into the film, which may totally internally reflect
+        let sin2_f = sin2_i * (n / self.ior).powi(2);
+        if sin2_f >= 1.0 {
+            return 1.0;
+        }
+        let cos_f = (1.0 - sin2_f).sqrt();
+        // And on into the base, with a complex angle for conductors
+        let n3 = Complex::new(eta, k);
+        let cos_b = (Complex::real(1.0) - Complex::real(sin2_i * n * n) / (n3 * n3)).sqrt();
+        let (n1, n2) = (Complex::real(n), Complex::real(self.ior));
+        let (c1, c2) = (Complex::real(cos_i), Complex::real(cos_f));
+        let fresnel = |na: Complex, ca: Complex, nb: Complex, cb: Complex| (
+            (na * ca - nb * cb) / (na * ca + nb * cb),
+            (nb * ca - na * cb) / (nb * ca + na * cb),
+        );
+        let (r12_s, r12_p) = fresnel(n1, c1, n2, c2);
+        let (r23_s, r23_p) = fresnel(n2, c2, n3, cos_b);
+        // The phase difference of a round trip through the film
+        let phase = 4.0 * PI * self.ior * self.thickness * cos_f / wavelength;
+        let shift = Complex::new(phase.cos(), phase.sin());
+        let airy = |r12: Complex, r23: Complex| {
+            ((r12 + r23 * shift) / (Complex::real(1.0) + r12 * r23 * shift)).norm()
+        };
+        (airy(r12_s, r23_s) + airy(r12_p, r23_p)) / 2.0
+    }
+
+    /// Returns the RGB reflectance of the film over a base, at a single
+    /// wavelength if the path carries one or otherwise integrated over the
+    /// visible spectrum
+    ///
+    /// # Arguments
+    /// * `cos_i`      - the cosine of the incident angle
+    /// * `n`          - the index of refraction of the incident medium
+    /// * `eta`        - the real part of the base's index of refraction
+    /// * `k`          - the imaginary part of the base's index of refraction
+    /// * `wavelength` - the wavelength carried by the path, if any
+    ///
+    pub fn reflectance_rgb(
+        &self,
+        cos_i: Float,
+        n: Float,
+        eta: Vec3,
+        k: Vec3,
+        wavelength: Option<Float>,
+    ) -> Vec3 {
+        let at = |l: Float| Vec3::new(
+            self.reflectance(cos_i, n, eta.x, k.x, l),
+            self.reflectance(cos_i, n, eta.y, k.y, l),
+            self.reflectance(cos_i, n, eta.z, k.z, l),
+        );
+        if let Some(l) = wavelength {
+            return at(l);
+        }
+        let mut sum = Vec3::zeros();
+        let mut total = Vec3::zeros();
+        for n in 0..RGB_WAVELENGTHS {
+            let u = (n as Float + 0.5) / (RGB_WAVELENGTHS as Float);
+            let l = WAVELENGTH_MIN + u * (WAVELENGTH_MAX - WAVELENGTH_MIN);
+            let weight = rgb_weight(l);
+            sum = sum + at(l) * weight;
+            total = total + weight;
+        }
+        let r = sum / total;
+        Vec3::new(r.x.max(0.0).min(1.0), r.y.max(0.0).min(1.0), r.z.max(0.0).min(1.0))
+    }
+
+    /// Returns the reflectance of the film over a base at each of the three
+    /// wavelengths traced by a spectral path
+    ///
+    /// # Arguments
+    /// * `cos_i`       - the cosine of the incident angle
+    /// * `n`           - the index of refraction of the incident medium
+    /// * `eta`         - the real part of the base's index of refraction at
+    ///                   each wavelength
+    /// * `k`           - the imaginary part of the base's index of refraction
+    ///                   at each wavelength
+    /// * `wavelengths` - the wavelengths in nanometers
+    ///
+    pub fn reflectance_spectrum(
+        &self,
+        cos_i: Float,
+        n: Float,
+        eta: Vec3,
+        k: Vec3,
+        wavelengths: Vec3,
+    ) -> Vec3 {
+        Vec3::new(
+            self.reflectance(cos_i, n, eta.x, k.x, wavelengths.x),
+            self.reflectance(cos_i, n, eta.y, k.y, wavelengths.y),
+            self.reflectance(cos_i, n, eta.z, k.z, wavelengths.z),
+        )
+    }
+}
+
+/// A minimal complex number for the film's amplitude coefficients
+#[derive(Debug, Clone, Copy)]
+struct Complex {
+    re: Float,
+    im: Float,
+}
+
+impl Complex {
+    fn new(re: Float, im: Float) -> Self {
+        Complex { re, im }
+    }
+
+    fn real(re: Float) -> Self {
+        Complex::new(re, 0.0)
+    }
+
+    /// The squared magnitude
+    fn norm(self) -> Float {
+        self.re * self.re + self.im * self.im
+    }
+
+    /// The principal square root
+    fn sqrt(self) -> Self {
+        let m = self.norm().sqrt();
+        let re = ((m + self.re) / 2.0).max(0.0).sqrt();
+        let im = ((m - self.re) / 2.0).max(0.0).sqrt();
+        Complex::new(re, if self.im < 0.0 { -im } else { im })
+    }
+}
+
+impl Add for Complex {
+    type Output = Self;
+    fn add(self, o: Self) -> Self {
+        Complex::new(self.re + o.re, self.im + o.im)
+    }
+}
+
+impl Sub for Complex {
+    type Output = Self;
+    fn sub(self, o: Self) -> Self {
+        Complex::new(self.re - o.re, self.im - o.im)
+    }
+}
+
+impl Mul for Complex {
+    type Output = Self;
+    fn mul(self, o: Self) -> Self {
+        Complex::new(self.re * o.re - self.im * o.im, self.re * o.im + self.im * o.re)
+    }
+}
+
+impl Div for Complex {
+    type Output = Self;
+    fn div(self, o: Self) -> Self {
+        let d = o.norm();
+        Complex::new(
+            (self.re * o.re + self.im * o.im) / d,
+            (self.im * o.re - self.re * o.im) / d,
+        )
+    }
+}
@@ -1,14 +1,121 @@
-use rand::distributions::{Range, Sample};
-use rand::thread_rng;
+use rand::random;
+
+/// The scalar type used by the math core. Building with the `f32` feature
+/// halves the memory footprint of vectors, rays and geometry at the cost of
+/// precision.
+#[cfg(feature = "f32")]
+pub type Float = f32;
+
+#[cfg(not(feature = "f32"))]
+pub type Float = f64;
+
+/// Archimedes' constant as a `Float`
+pub const PI: Float = ::std::f64::consts::PI as Float;
 
 /// Returns a random float between 0 and 1
-pub fn rand() -> f64 {
-    Range::new(0.0, 1.0).sample(&mut thread_rng())
+pub fn rand() -> Float {
+    random()
 }
 
 /// Schlick's approximation
-pub fn schlick(cosine: f64, ref_idx: f64) -> f64 {
+pub fn schlick(cosine: Float, ref_idx: Float) -> Float {
     let r = (1.0 - ref_idx) / (1.0 + ref_idx);
     let r2 = r * r;
     r2 + (1.0 - r2) * (1.0 - cosine).powf(5.0)
 }
+
+/// The machine epsilon of `Float`, ie. the bound on the relative error of a
+/// single rounded floating-point operation
+pub const MACHINE_EPSILON: Float = Float::EPSILON * 0.5;
+
+/// Returns a conservative bound on the relative error accumulated by `n`
+/// rounded floating-point operations
+pub fn gamma(n: u32) -> Float {
+    let n = n as Float;
+    (n * MACHINE_EPSILON) / (1.0 - n * MACHINE_EPSILON)
+}
+
+/// Returns the next representable float greater than `v`
+pub fn next_float_up(v: Float) -> Float {
+    if v.is_infinite() && v > 0.0 {
+        return v;
+    }
+    // Skip negative zero so the step is taken from positive zero
+    let v = if v == -0.0 { 0.0 } else { v };
+    let bits = v.to_bits();
+    Float::from_bits(if v >= 0.0 { bits + 1 } else { bits - 1 })
+}
+
+/// Returns the next representable float less than `v`
+pub fn next_float_down(v: Float) -> Float {
+    if v.is_infinite() && v < 0.0 {
+        return v;
+    }
+    // Skip positive zero so the step is taken from negative zero
+    let v = if v == 0.0 { -0.0 } else { v };
+    let bits = v.to_bits();
+    Float::from_bits(if v > 0.0 { bits - 1 } else { bits + 1 })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Finite values of either sign, including subnormals and the extremes
+    const VALUES: [Float; 10] = [
+        1.0, -1.0, 0.1, -3.5e7, 1e-30, -1e-30, Float::MIN_POSITIVE, -Float::MIN_POSITIVE,
+        Float::MAX, -Float::MAX,
+    ];
+
+    #[test]
+    fn gamma_grows_with_the_operation_count() {
+        assert_eq!(gamma(0), 0.0);
+        assert!(gamma(1) >= MACHINE_EPSILON);
+        for n in 1..64 {
+            assert!(gamma(n) < gamma(n + 1));
+            assert!(gamma(n) >= (n as Float) * MACHINE_EPSILON);
+        }
+    }
+
+    #[test]
+    fn stepping_from_zero_gives_the_smallest_subnormal() {
+        let smallest = Float::from_bits(1);
+        assert!(smallest > 0.0);
+        assert_eq!(next_float_up(0.0), smallest);
+        assert_eq!(next_float_up(-0.0), smallest);
+        assert_eq!(next_float_down(0.0), -smallest);
+        assert_eq!(next_float_down(-0.0), -smallest);
+    }
+
+    #[test]
+    fn stepping_saturates_at_infinity() {
+        assert_eq!(next_float_up(Float::INFINITY), Float::INFINITY);
+        assert_eq!(next_float_down(Float::NEG_INFINITY), Float::NEG_INFINITY);
+        assert_eq!(next_float_up(Float::MAX), Float::INFINITY);
+        assert_eq!(next_float_down(-Float::MAX), Float::NEG_INFINITY);
+        assert_eq!(next_float_up(Float::NEG_INFINITY), -Float::MAX);
+        assert_eq!(next_float_down(Float::INFINITY), Float::MAX);
+    }
+
+    #[test]
+    fn stepping_moves_by_one_ulp() {
+        assert_eq!(next_float_up(1.0), 1.0 + Float::EPSILON);
+        assert_eq!(next_float_down(-1.0), -1.0 - Float::EPSILON);
+        for &x in &VALUES {
+            assert!(next_float_up(x) > x);
+            assert!(next_float_down(x) < x);
+        }
+    }
+
+    #[test]
+    fn stepping_up_then_down_round_trips() {
+        for &x in VALUES.iter().chain(&[0.0, -0.0, Float::from_bits(1)]) {
+            if x != Float::MAX {
+                assert_eq!(next_float_down(next_float_up(x)), x);
+            }
+            if x != -Float::MAX {
+                assert_eq!(next_float_up(next_float_down(x)), x);
+            }
+        }
+    }
+}
@@ -1,18 +1,18 @@
 use color::Color;
 
 use std::ops::{Add, Div, Mul, Neg, Sub, Index};
-use util::rand;
+use util::{rand, Float};
 
 #[derive(Debug, Clone, Copy)]
 pub struct Vec3 {
-    pub x: f64,
-    pub y: f64,
-    pub z: f64,
+    pub x: Float,
+    pub y: Float,
+    pub z: Float,
 }
 
 impl Vec3 {
     /// Creates a new vector with components (x, y, z)
-    pub fn new(x: f64, y: f64, z: f64) -> Self {
+    pub fn new(x: Float, y: Float, z: Float) -> Self {
         Vec3 { x, y, z }
     }
 
@@ -26,8 +26,19 @@ impl Vec3 {
         Vec3::new(1.0, 1.0, 1.0)
     }
 
+    /// Creates a new vector with component k along the given axis, and
+    /// components a and b along the two remaining axes in order
+    pub fn from_axes(axis: u32, k: Float, a: Float, b: Float) -> Self {
+        match axis {
+            0 => Vec3::new(k, a, b),
+            1 => Vec3::new(a, k, b),
+            2 => Vec3::new(a, b, k),
+            _ => panic!("unexpected vector index {}!", axis),
+        }
+    }
+
     /// Returns the dot product of vectors u and v
-    pub fn dot(u: Vec3, v: Vec3) -> f64 {
+    pub fn dot(u: Vec3, v: Vec3) -> Float {
         u.x * v.x + u.y * v.y + u.z * v.z
     }
 
@@ -47,7 +58,7 @@ impl Vec3 {
 
     /// Returns the refraction of vector v pased the normal vector n if
     /// possible. k represents ni/nt.
-    pub fn refract(v: Vec3, n: Vec3, k: f64) -> Option<Self> {
+    pub fn refract(v: Vec3, n: Vec3, k: Float) -> Option<Self> {
         let uv = v.unit();
         let dt = Vec3::dot(uv, n);
         let d = 1.0 - k * k * (1.0 - dt * dt);
@@ -68,6 +79,11 @@ impl Vec3 {
         }
     }
 
+    /// Returns a random vector on the surface of the unit sphere
+    pub fn rand_unit() -> Self {
+        Vec3::rand().unit()
+    }
+
     /// Returns a racomd vector within the unit disc
     pub fn rand_disc() -> Self {
         loop {
@@ -78,23 +94,52 @@ impl Vec3 {
         }
     }
 
+    /// Returns two unit vectors which together with the unit vector w form an
+    /// orthonormal basis
+    pub fn basis(w: Vec3) -> (Self, Self) {
+        let a = if w.x.abs() > 0.9 {
+            Vec3::new(0.0, 1.0, 0.0)
+        } else {
+            Vec3::new(1.0, 0.0, 0.0)
+        };
+        let v = Vec3::cross(w, a).unit();
+        let u = Vec3::cross(w, v);
+        (u, v)
+    }
+
     /// Returns the magnitude of the vector, ie. its dot product with itself
-    pub fn mag(self) -> f64 {
+    pub fn mag(self) -> Float {
         Vec3::dot(self, self)
     }
 
     /// Returns the length of the vector
-    pub fn len(self) -> f64 {
+    pub fn len(self) -> Float {
         self.mag().sqrt()
     }
 
+    /// Returns the vector with the absolute value of each component
+    pub fn abs(self) -> Self {
+        Vec3::new(self.x.abs(), self.y.abs(), self.z.abs())
+    }
+
+    /// Returns the vector with the exponential function applied to each
+    /// component
+    pub fn exp(self) -> Self {
+        Vec3::new(self.x.exp(), self.y.exp(), self.z.exp())
+    }
+
     /// Returns an equivalent unit vector
     pub fn unit(self) -> Self {
         self / self.len()
     }
 
+    /// Returns the luminance of the vector as a linear RGB color
+    pub fn luminance(self) -> Float {
+        0.2126 * self.x + 0.7152 * self.y + 0.0722 * self.z
+    }
+
     /// Converts the current vector to a color given a specific gamma correction
-    pub fn color(&self, gamma: f64) -> Color {
+    pub fn color(&self, gamma: Float) -> Color {
         Color::fnew(self.x, self.y, self.z, gamma)
     }
 }
@@ -107,8 +152,8 @@ impl Neg for Vec3 {
 }
 
 impl Index<u32> for Vec3 {
-    type Output = f64;
-    fn index(&self, i: u32) -> &f64 {
+    type Output = Float;
+    fn index(&self, i: u32) -> &Float {
         match i {
             0 => &self.x,
             1 => &self.y,
@@ -130,16 +175,16 @@ macro_rules! vec3_binary_op {
             }
         }
 
-        // Vec3 op f64
-        impl $trait<f64> for Vec3 {
+        // Vec3 op Float
+        impl $trait<Float> for Vec3 {
             type Output = Vec3;
-            fn $func(self, f: f64) -> Vec3 {
+            fn $func(self, f: Float) -> Vec3 {
                 Vec3::new(self.x $op f, self.y $op f, self.z $op f)
             }
         }
 
-        // f64 op Vec3
-        impl $trait<Vec3> for f64 {
+        // Float op Vec3
+        impl $trait<Vec3> for Float {
             type Output = Vec3;
             fn $func(self, v: Vec3) -> Vec3 {
                 Vec3::new(self $op v.x, self $op v.y, self $op v.z)